@@ -0,0 +1,240 @@
+use crate::effects::PickupKind;
+use crate::events::GameEvent;
+use crate::score::RunSummary;
+use crate::storage::Storage;
+use crate::ui::{Anchor, Ui, GOLD, SCREEN, WHITE};
+use crate::EnemyKind;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use web_sys::CanvasRenderingContext2d;
+
+const STORAGE_KEY: &str = "achievements";
+const TOAST_TIME: f64 = 3.0;  // Seconds each unlock stays on screen
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Condition {
+    Distance(u32),  // Meters in one run
+    UntouchedDistance(u32),  // Meters in one run without taking a hit
+    GoblinsDefeated(u32),  // Across every run
+    BrutesDefeated(u32),
+    Parries(u32),
+    Combo(u32),  // Kills chained in one combo
+    FullHealthHeart,  // A heart picked up with nothing to heal
+}
+
+pub struct AchievementDef {
+    pub id: &'static str,  // Key in the saved unlocks, never shown
+    pub name: &'static str,
+    pub description: &'static str,
+    pub condition: Condition,
+}
+
+pub const ACHIEVEMENTS: [AchievementDef; 8] = [
+    AchievementDef {
+        id: "first_steps",
+        name: "First Steps",
+        description: "Reach 100m",
+        condition: Condition::Distance(100),
+    },
+    AchievementDef {
+        id: "long_road",
+        name: "The Long Road",
+        description: "Reach 1000m",
+        condition: Condition::Distance(1000),
+    },
+    AchievementDef {
+        id: "untouchable",
+        name: "Untouchable",
+        description: "Reach 1000m without taking damage",
+        condition: Condition::UntouchedDistance(1000),
+    },
+    AchievementDef {
+        id: "goblin_slayer",
+        name: "Goblin Slayer",
+        description: "Defeat 50 goblins",
+        condition: Condition::GoblinsDefeated(50),
+    },
+    AchievementDef {
+        id: "giant_killer",
+        name: "Giant Killer",
+        description: "Defeat a brute",
+        condition: Condition::BrutesDefeated(1),
+    },
+    AchievementDef {
+        id: "riposte",
+        name: "Riposte",
+        description: "Parry 10 attacks",
+        condition: Condition::Parries(10),
+    },
+    AchievementDef {
+        id: "whirlwind",
+        name: "Whirlwind",
+        description: "Chain a 5 kill combo",
+        condition: Condition::Combo(5),
+    },
+    AchievementDef {
+        id: "glutton",
+        name: "Glutton",
+        description: "Collect a heart at full health",
+        condition: Condition::FullHealthHeart,
+    },
+];
+
+// Unlocks and the lifetime counters behind them
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Achievements {
+    unlocked: BTreeSet<String>,  // Ids, so the table can be reordered
+    goblins_defeated: u32,
+    brutes_defeated: u32,
+    parries: u32,
+    #[serde(skip)]
+    toasts: Vec<(usize, f64)>,  // Index into ACHIEVEMENTS and seconds left, oldest first
+}
+
+impl Achievements {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .get(STORAGE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set(STORAGE_KEY, &serde_json::to_string(self).unwrap_or_default());
+    }
+
+    pub fn is_unlocked(&self, achievement: &AchievementDef) -> bool {
+        self.unlocked.contains(achievement.id)
+    }
+
+    pub fn unlocked_count(&self) -> usize {
+        ACHIEVEMENTS.iter().filter(|achievement| self.is_unlocked(achievement)).count()
+    }
+
+    // How far a counted achievement has got, as (count, goal)
+    pub fn progress(&self, achievement: &AchievementDef) -> Option<(u32, u32)> {
+        let (count, goal) = match achievement.condition {
+            Condition::GoblinsDefeated(goal) => (self.goblins_defeated, goal),
+            Condition::BrutesDefeated(goal) => (self.brutes_defeated, goal),
+            Condition::Parries(goal) => (self.parries, goal),
+            _ => return None,
+        };
+        Some((count.min(goal), goal))
+    }
+
+    // Takes a tick's events and the run so far. Returns true when anything
+    // changed that should be saved.
+    pub fn record(&mut self, events: &[GameEvent], run: &RunSummary) -> bool {
+        let mut changed = false;
+        let mut full_health_heart = false;
+        for event in events {
+            match *event {
+                GameEvent::EnemyKilled { kind: EnemyKind::Goblin, .. } => self.goblins_defeated += 1,
+                GameEvent::EnemyKilled { kind: EnemyKind::Brute, .. } => self.brutes_defeated += 1,
+                GameEvent::ShieldBlocked { parried: true } => self.parries += 1,
+                GameEvent::PickupCollected { kind: PickupKind::Heart, at_full_health: true, .. } => {
+                    full_health_heart = true;
+                    continue;
+                }
+                _ => continue,
+            }
+            changed = true;
+        }
+
+        for (index, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            if self.is_unlocked(achievement) {
+                continue;
+            }
+            let met = match achievement.condition {
+                Condition::Distance(meters) => run.distance >= meters,
+                Condition::UntouchedDistance(meters) => run.distance >= meters && run.damage_taken == 0.0,
+                Condition::GoblinsDefeated(count) => self.goblins_defeated >= count,
+                Condition::BrutesDefeated(count) => self.brutes_defeated >= count,
+                Condition::Parries(count) => self.parries >= count,
+                Condition::Combo(kills) => run.max_combo >= kills,
+                Condition::FullHealthHeart => full_health_heart,
+            };
+            if met {
+                self.unlocked.insert(achievement.id.to_string());
+                self.toasts.push((index, TOAST_TIME));
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        if let Some((_, time_left)) = self.toasts.first_mut() {
+            *time_left -= dt;
+            if *time_left <= 0.0 {
+                self.toasts.remove(0);
+            }
+        }
+    }
+
+    // Banner for the latest unlock, drawn over every scene
+    pub fn render_toast(&self, ctx: &CanvasRenderingContext2d) {
+        let Some(&(index, _)) = self.toasts.first() else {
+            return;
+        };
+        let banner = SCREEN.place(Anchor::Bottom, 0.0, -70.0, 360.0, 56.0);
+        let mut ui = Ui::new();
+        ui.panel(banner, "#1B1F2A", 0.92)
+            .label(banner, Anchor::Top, 0.0, 8.0, "ACHIEVEMENT UNLOCKED", 1.0, GOLD)
+            .label(banner, Anchor::Bottom, 0.0, -10.0, ACHIEVEMENTS[index].name, 3.0, WHITE)
+            .render(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn goblin_killed() -> GameEvent {
+        GameEvent::EnemyKilled { kind: EnemyKind::Goblin, x: 0.0, y: 0.0, airborne: false }
+    }
+
+    #[test]
+    fn run_statistics_unlock_once() {
+        let mut achievements = Achievements::default();
+        let mut run = RunSummary { distance: 150, max_combo: 5, ..RunSummary::default() };
+        assert!(achievements.record(&[], &run));
+        assert_eq!(achievements.unlocked_count(), 2);
+        assert_eq!(achievements.toasts.len(), 2);
+        assert!(!achievements.record(&[], &run));
+
+        // Taking a hit rules out Untouchable, but not The Long Road
+        run.distance = 1000;
+        run.damage_taken = 0.25;
+        achievements.record(&[], &run);
+        assert!(achievements.is_unlocked(&ACHIEVEMENTS[1]));
+        assert!(!achievements.is_unlocked(&ACHIEVEMENTS[2]));
+
+        // Banners queue up and leave oldest first
+        assert_eq!(achievements.toasts.len(), 3);
+        achievements.update(TOAST_TIME + 0.1);
+        assert_eq!(achievements.toasts[0].0, 6);
+    }
+
+    #[test]
+    fn lifetime_counters_persist_between_sessions() {
+        let mut storage = MemoryStorage::default();
+        let run = RunSummary::default();
+        let mut achievements = Achievements::load(&storage);
+        achievements.record(&[goblin_killed(); 30], &run);
+        achievements.save(&mut storage);
+
+        let mut achievements = Achievements::load(&storage);
+        assert_eq!(achievements.progress(&ACHIEVEMENTS[3]), Some((30, 50)));
+        assert!(achievements.toasts.is_empty());
+        achievements.record(&[goblin_killed(); 20], &run);
+        assert!(achievements.is_unlocked(&ACHIEVEMENTS[3]));
+
+        let heart = GameEvent::PickupCollected { kind: PickupKind::Heart, x: 0.0, y: 0.0, at_full_health: true };
+        achievements.record(&[heart], &run);
+        assert!(achievements.is_unlocked(&ACHIEVEMENTS[7]));
+        assert_eq!(achievements.progress(&ACHIEVEMENTS[7]), None);
+    }
+}
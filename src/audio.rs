@@ -0,0 +1,130 @@
+use crate::music::{self, Track, BOSS_TEMPO};
+use crate::sfx::{self, Sfx};
+use std::collections::HashMap;
+use web_sys::{AudioBuffer, AudioBufferSourceNode, AudioContext, AudioScheduledSourceNode, GainNode};
+
+// A track and whether it plays at the boss tempo
+pub type Tune = (Track, bool);
+
+struct Music {
+    tune: Tune,
+    source: AudioBufferSourceNode,
+    started_at: f64,  // Context time the loop was at its start
+    duration: f64,  // Seconds per pass
+}
+
+// Plays synthesized effects and music through Web Audio. Browsers only
+// allow audio to start from a user gesture, so the context is created by
+// the first key or touch and stays silent until then.
+#[derive(Default)]
+pub struct AudioPlayer {
+    context: Option<AudioContext>,
+    buffers: HashMap<Sfx, AudioBuffer>,  // Synthesized once, when the context is created
+    loops: HashMap<Tune, AudioBuffer>,  // Rendered up front, as a loop takes too long to make mid-run
+    music_gain: Option<GainNode>,
+    music: Option<Music>,
+}
+
+impl AudioPlayer {
+    // Called from input handlers; cheap once the context is running
+    pub fn unlock(&mut self) {
+        if self.context.is_none() {
+            self.context = AudioContext::new().ok();
+            if let Some(context) = &self.context {
+                self.buffers = Sfx::ALL
+                    .iter()
+                    .filter_map(|&sound| Some((sound, render(context, sound)?)))
+                    .collect();
+                self.loops = Track::ALL
+                    .iter()
+                    .flat_map(|&track| [(track, false), (track, true)])
+                    // The title never plays at the boss tempo
+                    .filter(|&(track, boss)| !(boss && track == Track::Title))
+                    .filter_map(|tune| Some((tune, render_music(context, tune)?)))
+                    .collect();
+                self.music_gain = context.create_gain().ok().filter(|gain| {
+                    gain.connect_with_audio_node(&context.destination()).is_ok()
+                });
+            }
+        }
+        if let Some(context) = &self.context {
+            // The promise settles on its own; a failed resume just stays silent
+            let _ = context.resume();
+        }
+    }
+
+    // volume is 0.0..=1.0, from the sound setting
+    pub fn play(&self, sound: Sfx, volume: f64) {
+        if volume <= 0.0 {
+            return;
+        }
+        let (Some(context), Some(buffer)) = (&self.context, self.buffers.get(&sound)) else {
+            return;
+        };
+        let (Ok(source), Ok(gain)) = (context.create_buffer_source(), context.create_gain()) else {
+            return;
+        };
+        source.set_buffer(Some(buffer));
+        gain.gain().set_value(volume as f32);
+        if source.connect_with_audio_node(&gain).is_ok()
+            && gain.connect_with_audio_node(&context.destination()).is_ok()
+        {
+            let _ = source.start();
+        }
+    }
+
+    // Called every frame with the tune the scene wants, or None for silence.
+    // Tunes loop until told otherwise.
+    pub fn play_music(&mut self, tune: Option<Tune>, volume: f64) {
+        let (Some(context), Some(gain)) = (&self.context, &self.music_gain) else {
+            return;
+        };
+        gain.gain().set_value(volume as f32);
+        if self.music.as_ref().map(|music| music.tune) == tune {
+            return;
+        }
+
+        let now = context.current_time();
+        let previous = self.music.take().map(|music| {
+            let _ = AudioScheduledSourceNode::stop(&music.source);
+            (music.tune.0, ((now - music.started_at) / music.duration).fract())
+        });
+        let Some(tune) = tune else {
+            return;
+        };
+        // Speeding up or slowing down picks the song up where it was
+        let progress = match previous {
+            Some((track, progress)) if track == tune.0 => progress,
+            _ => 0.0,
+        };
+
+        let (Some(buffer), Ok(source)) = (self.loops.get(&tune), context.create_buffer_source()) else {
+            return;
+        };
+        source.set_buffer(Some(buffer));
+        source.set_loop(true);
+        let duration = buffer.duration();
+        if source.connect_with_audio_node(gain).is_ok()
+            && source.start_with_when_and_grain_offset(0.0, progress * duration).is_ok()
+        {
+            self.music = Some(Music { tune, source, started_at: now - progress * duration, duration });
+        }
+    }
+}
+
+fn render_music(context: &AudioContext, (track, boss): Tune) -> Option<AudioBuffer> {
+    let rate = context.sample_rate();
+    let tempo = if boss { BOSS_TEMPO } else { 1.0 };
+    let samples = music::render_loop(&track.song(), rate as u32, tempo);
+    let buffer = context.create_buffer(1, samples.len() as u32, rate).ok()?;
+    buffer.copy_to_channel(&samples, 0).ok()?;
+    Some(buffer)
+}
+
+fn render(context: &AudioContext, sound: Sfx) -> Option<AudioBuffer> {
+    let rate = context.sample_rate();
+    let samples = sfx::synthesize(&sound.params(), rate as u32);
+    let buffer = context.create_buffer(1, samples.len() as u32, rate).ok()?;
+    buffer.copy_to_channel(&samples, 0).ok()?;
+    Some(buffer)
+}
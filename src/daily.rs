@@ -0,0 +1,128 @@
+use crate::difficulty::Difficulty;
+use crate::Player;
+use serde::{Deserialize, Serialize};
+
+// Everyone plays the daily on the same preset with assists off, so its
+// board compares like with like
+pub const DAILY_DIFFICULTY: Difficulty = Difficulty::Knight;
+
+// Gravity multiplier while the low gravity modifier is on
+const LOW_GRAVITY_SCALE: f64 = 0.6;
+
+// Twists on a run. Standard and lives runs play with none of them on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Modifiers {
+    pub low_gravity: bool,
+    pub double_enemies: bool,
+    pub one_hit_death: bool,  // Any hit that lands ends the run
+    pub no_hearts: bool,  // Heart pickups never spawn
+}
+
+impl Modifiers {
+    // Scales the chance of an enemy on each generated platform
+    pub fn enemy_rate(self) -> f64 {
+        if self.double_enemies { 2.0 } else { 1.0 }
+    }
+
+    // Scales the weight of hearts in the pickup table
+    pub fn heart_rate(self) -> f64 {
+        if self.no_hearts { 0.0 } else { 1.0 }
+    }
+
+    pub fn apply(self, player: &mut Player) {
+        if self.low_gravity {
+            player.movement.gravity *= LOW_GRAVITY_SCALE;
+        }
+    }
+
+    // Names of the modifiers that are on, for the title and HUD
+    pub fn names(self) -> Vec<&'static str> {
+        [
+            (self.low_gravity, "Low gravity"),
+            (self.double_enemies, "Double enemies"),
+            (self.one_hit_death, "One-hit death"),
+            (self.no_hearts, "No hearts"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect()
+    }
+}
+
+// The run everyone gets on a given day
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyChallenge {
+    pub date: String,  // YYYY-MM-DD, as passed from the page
+    pub seed: u32,
+    pub modifiers: Modifiers,
+}
+
+impl DailyChallenge {
+    pub fn for_date(date: &str) -> Result<Self, String> {
+        let bytes = date.as_bytes();
+        let well_formed = bytes.len() == 10
+            && bytes.iter().enumerate().all(|(i, byte)| match i {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+        if !well_formed {
+            return Err(format!("Expected a YYYY-MM-DD date, got {:?}", date));
+        }
+
+        // FNV-1a over the date, so every player gets the same run
+        let mut hash: u32 = 0x811c9dc5;
+        for byte in bytes {
+            hash ^= *byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+        // The level generator needs a non-zero seed
+        let seed = (hash & 0x7fffffff).max(1);
+
+        // The top bits pick the modifiers; a day with none gets one of them
+        let mut bits = hash.wrapping_mul(0x9e3779b1) >> 28;
+        if bits == 0 {
+            bits = 1 << (hash % 4);
+        }
+        let modifiers = Modifiers {
+            low_gravity: bits & 1 != 0,
+            double_enemies: bits & 2 != 0,
+            one_hit_death: bits & 4 != 0,
+            no_hearts: bits & 8 != 0,
+        };
+
+        Ok(DailyChallenge { date: date.to_string(), seed, modifiers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_give_the_same_challenge_every_time() {
+        let today = DailyChallenge::for_date("2026-10-18").unwrap();
+        assert_eq!(DailyChallenge::for_date("2026-10-18").unwrap(), today);
+        assert_ne!(DailyChallenge::for_date("2026-10-19").unwrap().seed, today.seed);
+
+        // Every day of a year has a twist, and the days don't all share one
+        let mut seen = Vec::new();
+        for day in 0..12 * 28 {
+            let date = format!("2027-{:02}-{:02}", day / 28 + 1, day % 28 + 1);
+            let challenge = DailyChallenge::for_date(&date).unwrap();
+            assert!(!challenge.modifiers.names().is_empty(), "{}", date);
+            assert!(challenge.seed > 0);
+            if !seen.contains(&challenge.modifiers) {
+                seen.push(challenge.modifiers);
+            }
+        }
+        assert!(seen.len() > 8);
+    }
+
+    #[test]
+    fn rejects_malformed_dates() {
+        for date in ["", "2026-1-18", "18/10/2026", "2026-10-18T00:00", "2026_10_18"] {
+            assert!(DailyChallenge::for_date(date).is_err(), "{}", date);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::Player;
+use serde::{Deserialize, Serialize};
+
+// Game speed while the slow motion assist is on
+pub const SLOW_MOTION_SPEED: f64 = 0.6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Squire,
+    #[default]
+    Knight,
+    Paladin,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Squire, Difficulty::Knight, Difficulty::Paladin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Squire => "Squire",
+            Difficulty::Knight => "Knight",
+            Difficulty::Paladin => "Paladin",
+        }
+    }
+
+    // Stored with high scores
+    pub fn id(self) -> &'static str {
+        match self {
+            Difficulty::Squire => "squire",
+            Difficulty::Knight => "knight",
+            Difficulty::Paladin => "paladin",
+        }
+    }
+
+    // Scales the chance of an enemy on each generated platform
+    pub fn enemy_rate(self) -> f64 {
+        match self {
+            Difficulty::Squire => 0.6,
+            Difficulty::Knight => 1.0,
+            Difficulty::Paladin => 1.4,
+        }
+    }
+
+    // Quarter hearts lost per enemy hit
+    pub fn enemy_damage(self) -> f64 {
+        match self {
+            Difficulty::Squire => 0.5,
+            Difficulty::Knight => 1.0,
+            Difficulty::Paladin => 2.0,
+        }
+    }
+
+    // Scales the weight of hearts in the pickup table
+    pub fn heart_rate(self) -> f64 {
+        match self {
+            Difficulty::Squire => 1.5,
+            Difficulty::Knight => 1.0,
+            Difficulty::Paladin => 0.5,
+        }
+    }
+
+    // Scales the invincibility after a hit
+    pub fn iframe_scale(self) -> f64 {
+        match self {
+            Difficulty::Squire => 1.5,
+            Difficulty::Knight => 1.0,
+            Difficulty::Paladin => 0.7,
+        }
+    }
+
+    pub fn apply(self, player: &mut Player) {
+        player.hit_damage = self.enemy_damage();
+        player.iframe_duration *= self.iframe_scale();
+    }
+}
+
+// Accessibility helpers; they can be switched at any time, even mid-run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Assists {
+    pub slow_motion: bool,
+    pub infinite_health: bool,
+    pub auto_attack: bool,
+}
+
+impl Assists {
+    pub fn any(self) -> bool {
+        self.slow_motion || self.infinite_health || self.auto_attack
+    }
+
+    // Every assist on in either set
+    pub fn union(self, other: Assists) -> Assists {
+        Assists {
+            slow_motion: self.slow_motion || other.slow_motion,
+            infinite_health: self.infinite_health || other.infinite_health,
+            auto_attack: self.auto_attack || other.auto_attack,
+        }
+    }
+
+    // Names of the assists that are on, as stored with high scores
+    pub fn names(self) -> Vec<String> {
+        [
+            (self.slow_motion, "slow motion"),
+            (self.infinite_health, "infinite health"),
+            (self.auto_attack, "auto-attack"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| name.to_string())
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_get_harder_in_order() {
+        for pair in Difficulty::ALL.windows(2) {
+            let (easier, harder) = (pair[0], pair[1]);
+            assert!(easier.enemy_rate() < harder.enemy_rate());
+            assert!(easier.enemy_damage() < harder.enemy_damage());
+            assert!(easier.heart_rate() > harder.heart_rate());
+            assert!(easier.iframe_scale() > harder.iframe_scale());
+        }
+    }
+
+    #[test]
+    fn assists_used_accumulate() {
+        let used = Assists::default().union(Assists { slow_motion: true, ..Assists::default() });
+        let used = used.union(Assists { auto_attack: true, ..Assists::default() });
+        assert!(used.any());
+        assert_eq!(used.names(), vec!["slow motion", "auto-attack"]);
+        assert!(!Assists::default().any());
+    }
+}
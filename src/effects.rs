@@ -0,0 +1,179 @@
+use crate::inventory::ItemKind;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EffectKind {
+    Invincibility,
+    SpeedBoots,
+    FlamingSword,
+    Magnet,
+    Shield,  // Absorbs the next hit, then breaks
+    Poison,  // Drains a quarter heart at a time; cured by a potion
+}
+
+impl EffectKind {
+    // Seconds the effect lasts once picked up
+    pub fn duration(self) -> f64 {
+        match self {
+            EffectKind::Invincibility => 8.0,
+            EffectKind::SpeedBoots => 10.0,
+            EffectKind::FlamingSword => 12.0,
+            EffectKind::Magnet => 15.0,
+            EffectKind::Shield => 30.0,
+            EffectKind::Poison => 6.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectKind::Invincibility => "INVINCIBLE",
+            EffectKind::SpeedBoots => "SPEED BOOTS",
+            EffectKind::FlamingSword => "FLAMING SWORD",
+            EffectKind::Magnet => "COIN MAGNET",
+            EffectKind::Shield => "SHIELD",
+            EffectKind::Poison => "POISONED",
+        }
+    }
+
+    // HUD icon letter and color
+    pub fn icon(self) -> (&'static str, &'static str) {
+        match self {
+            EffectKind::Invincibility => ("*", "#FFFF66"),
+            EffectKind::SpeedBoots => ("S", "#1E90FF"),
+            EffectKind::FlamingSword => ("F", "#FF4500"),
+            EffectKind::Magnet => ("M", "#C0C0C0"),
+            EffectKind::Shield => ("O", "#00CED1"),
+            EffectKind::Poison => ("P", "#9ACD32"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub kind: EffectKind,
+    pub remaining: f64,  // Seconds
+}
+
+// Timed power-ups currently on the knight
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActiveEffects {
+    effects: Vec<ActiveEffect>,
+}
+
+impl ActiveEffects {
+    // Picking up an effect that is already running restarts its timer
+    pub fn grant(&mut self, kind: EffectKind) {
+        self.remove(kind);
+        self.effects.push(ActiveEffect { kind, remaining: kind.duration() });
+    }
+
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // Returns true if the effect was active
+    pub fn remove(&mut self, kind: EffectKind) -> bool {
+        let before = self.effects.len();
+        self.effects.retain(|effect| effect.kind != kind);
+        self.effects.len() != before
+    }
+
+    pub fn tick(&mut self, dt: f64) {
+        for effect in &mut self.effects {
+            effect.remaining -= dt;
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ActiveEffect> {
+        self.effects.iter()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PickupKind {
+    Heart,  // Heals one heart
+    HeartContainer,  // Raises max health by one heart
+    PowerUp(EffectKind),
+    Item(ItemKind),  // Goes into the inventory
+}
+
+pub struct SpawnEntry {
+    pub kind: PickupKind,
+    pub weight: u32,
+    pub min_meters: u32,  // Not rolled before the knight has come this far
+}
+
+// Chance per generated platform of a pickup hovering above it
+pub const PICKUP_CHANCE: u32 = 22;  // Percent
+
+pub const SPAWN_TABLE: [SpawnEntry; 10] = [
+    SpawnEntry { kind: PickupKind::Heart, weight: 60, min_meters: 0 },
+    SpawnEntry { kind: PickupKind::HeartContainer, weight: 3, min_meters: 200 },
+    SpawnEntry { kind: PickupKind::PowerUp(EffectKind::SpeedBoots), weight: 10, min_meters: 0 },
+    SpawnEntry { kind: PickupKind::PowerUp(EffectKind::Magnet), weight: 10, min_meters: 0 },
+    SpawnEntry { kind: PickupKind::PowerUp(EffectKind::FlamingSword), weight: 10, min_meters: 100 },
+    SpawnEntry { kind: PickupKind::PowerUp(EffectKind::Shield), weight: 6, min_meters: 150 },
+    SpawnEntry { kind: PickupKind::PowerUp(EffectKind::Invincibility), weight: 4, min_meters: 300 },
+    SpawnEntry { kind: PickupKind::Item(ItemKind::Potion), weight: 10, min_meters: 0 },
+    SpawnEntry { kind: PickupKind::Item(ItemKind::Dagger), weight: 10, min_meters: 50 },
+    SpawnEntry { kind: PickupKind::Item(ItemKind::Bomb), weight: 6, min_meters: 150 },
+];
+
+// Weighted pick among the entries available at this distance, with the
+// weight of hearts scaled by the difficulty
+pub fn roll_pickup(roll: u32, meters: u32, heart_rate: f64) -> PickupKind {
+    let weight = |entry: &SpawnEntry| match entry.kind {
+        PickupKind::Heart => (entry.weight as f64 * heart_rate).round() as u32,
+        _ => entry.weight,
+    };
+    let available = || SPAWN_TABLE.iter().filter(|entry| meters >= entry.min_meters);
+    let total: u32 = available().map(weight).sum();
+    let mut roll = roll % total;
+    for entry in available() {
+        if roll < weight(entry) {
+            return entry.kind;
+        }
+        roll -= weight(entry);
+    }
+    PickupKind::Heart
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_expire_and_refresh() {
+        let mut effects = ActiveEffects::default();
+        effects.grant(EffectKind::SpeedBoots);
+        effects.tick(9.0);
+        assert!(effects.has(EffectKind::SpeedBoots));
+
+        effects.grant(EffectKind::SpeedBoots);
+        effects.tick(9.0);
+        assert!(effects.has(EffectKind::SpeedBoots));
+        assert_eq!(effects.iter().count(), 1);
+
+        effects.tick(1.0);
+        assert!(!effects.has(EffectKind::SpeedBoots));
+        assert!(!effects.remove(EffectKind::SpeedBoots));
+    }
+
+    #[test]
+    fn spawn_table_respects_distance() {
+        let early: Vec<_> = (0..1000).map(|roll| roll_pickup(roll, 0, 1.0)).collect();
+        assert!(early.contains(&PickupKind::Heart));
+        assert!(early.contains(&PickupKind::PowerUp(EffectKind::Magnet)));
+        assert!(early.contains(&PickupKind::Item(ItemKind::Potion)));
+        assert!(!early.contains(&PickupKind::PowerUp(EffectKind::Invincibility)));
+        assert!(!early.contains(&PickupKind::Item(ItemKind::Bomb)));
+
+        let late: Vec<_> = (0..1000).map(|roll| roll_pickup(roll, 500, 1.0)).collect();
+        assert!(late.contains(&PickupKind::PowerUp(EffectKind::Invincibility)));
+        assert!(late.contains(&PickupKind::Item(ItemKind::Bomb)));
+
+        let hearts = |heart_rate| (0..1000).filter(|roll| roll_pickup(*roll, 500, heart_rate) == PickupKind::Heart).count();
+        assert!(hearts(0.5) < hearts(1.0) && hearts(1.0) < hearts(1.5));
+    }
+}
@@ -0,0 +1,66 @@
+use crate::effects::PickupKind;
+use crate::inventory::ItemKind;
+use crate::{Ability, EnemyKind};
+use serde::Serialize;
+
+// Meters between DistanceMilestone events
+pub const MILESTONE_METERS: u32 = 100;
+
+// Something that happened in the simulation this tick. Game::update queues
+// them; the score and particles react inside the game, then the engine
+// drains the queue for sound and the page's callback. Positions are in
+// world pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    PlayerJumped,
+    SwordSwung,
+    EnemyHit { x: f64, y: f64 },
+    EnemyKilled { kind: EnemyKind, x: f64, y: f64, airborne: bool },
+    PlayerDamaged { quarters: f64, health: f64, x: f64, y: f64 },
+    ShieldBlocked { parried: bool },
+    PickupCollected { kind: PickupKind, x: f64, y: f64, at_full_health: bool },
+    CoinCollected,
+    BombExploded { x: f64, y: f64 },
+    ItemUsed { item: ItemKind },
+    CheckpointReached,
+    LevelUp { level: u32 },
+    AbilityUnlocked { ability: Ability },
+    PlayerDied,
+    PlayerRespawned { lives: u32 },
+    DistanceMilestone { meters: u32 },
+}
+
+impl GameEvent {
+    pub fn to_json(self) -> String {
+        serde_json::to_string(&self).unwrap_or_default()
+    }
+}
+
+// Milestones passed when the distance goes from `before` to `after` meters
+pub fn milestones_between(before: u32, after: u32) -> impl Iterator<Item = u32> {
+    (before / MILESTONE_METERS + 1..=after / MILESTONE_METERS).map(|step| step * MILESTONE_METERS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_serialize_tagged_by_type() {
+        assert_eq!(GameEvent::PlayerJumped.to_json(), "{\"type\":\"PlayerJumped\"}");
+        let killed = GameEvent::EnemyKilled { kind: EnemyKind::Brute, x: 1.0, y: 2.5, airborne: true };
+        assert_eq!(
+            killed.to_json(),
+            "{\"type\":\"EnemyKilled\",\"kind\":\"Brute\",\"x\":1.0,\"y\":2.5,\"airborne\":true}"
+        );
+    }
+
+    #[test]
+    fn milestones_fire_once_per_hundred_meters() {
+        assert_eq!(milestones_between(0, 99).count(), 0);
+        assert_eq!(milestones_between(99, 100).collect::<Vec<_>>(), vec![100]);
+        assert_eq!(milestones_between(100, 100).count(), 0);
+        assert_eq!(milestones_between(150, 420).collect::<Vec<_>>(), vec![200, 300, 400]);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::input::{Action, ACTION_COUNT};
+use wasm_bindgen::JsCast;
+
+// Plain copy of a pad's state so the mapping can be tested without a browser
+#[derive(Clone, Debug, Default)]
+pub struct GamepadSnapshot {
+    pub axes: Vec<f64>,
+    pub buttons: Vec<bool>,
+}
+
+// Actions and analog run speed produced from one or more pads
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GamepadInput {
+    held: [bool; ACTION_COUNT],
+    pub move_axis: f64,  // -1.0..=1.0 after the dead zone, 0.0 when centered
+}
+
+impl GamepadInput {
+    pub fn held(&self, action: Action) -> bool {
+        self.held[action as usize]
+    }
+
+    // Combine with another pad so either one can drive the knight
+    pub fn merge(&mut self, other: &GamepadInput) {
+        for (held, other_held) in self.held.iter_mut().zip(other.held) {
+            *held |= other_held;
+        }
+        if other.move_axis.abs() > self.move_axis.abs() {
+            self.move_axis = other.move_axis;
+        }
+    }
+}
+
+// Button indices follow the W3C "standard" gamepad layout
+pub struct GamepadMapping {
+    pub dead_zone: f64,
+    pub buttons: Vec<(usize, Action)>,
+    pub horizontal_axis: usize,
+    pub vertical_axis: usize,
+}
+
+impl Default for GamepadMapping {
+    fn default() -> Self {
+        GamepadMapping {
+            dead_zone: 0.25,
+            buttons: vec![
+                (0, Action::Jump),    // A / Cross
+                (1, Action::Dash),    // B / Circle
+                (2, Action::Attack),  // X / Square
+                (3, Action::UseItem), // Y / Triangle
+                (4, Action::Block),   // LB / L1
+                (5, Action::Block),   // RB / R1
+                (6, Action::CycleItem), // LT / L2
+                (9, Action::Pause),   // Start / Options
+                (12, Action::Jump),   // D-pad up
+                (13, Action::Down),   // D-pad down
+                (14, Action::Left),   // D-pad left
+                (15, Action::Right),  // D-pad right
+            ],
+            horizontal_axis: 0,
+            vertical_axis: 1,
+        }
+    }
+}
+
+impl GamepadMapping {
+    // Rescale so the output ramps from 0 at the dead zone edge to 1 at full tilt
+    fn apply_dead_zone(&self, value: f64) -> f64 {
+        let magnitude = value.abs();
+        if magnitude <= self.dead_zone {
+            0.0
+        } else {
+            value.signum() * ((magnitude - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0)
+        }
+    }
+
+    pub fn map(&self, snapshot: &GamepadSnapshot) -> GamepadInput {
+        let mut input = GamepadInput::default();
+
+        for &(button, action) in &self.buttons {
+            if snapshot.buttons.get(button).copied().unwrap_or(false) {
+                input.held[action as usize] = true;
+            }
+        }
+
+        let axis = |index: usize| snapshot.axes.get(index).copied().unwrap_or(0.0);
+        let stick_x = self.apply_dead_zone(axis(self.horizontal_axis));
+        let stick_y = self.apply_dead_zone(axis(self.vertical_axis));
+
+        if stick_x < 0.0 {
+            input.held[Action::Left as usize] = true;
+        } else if stick_x > 0.0 {
+            input.held[Action::Right as usize] = true;
+        }
+        // Crouching needs a deliberate push rather than a slightly tilted stick
+        if stick_y > 0.5 {
+            input.held[Action::Down as usize] = true;
+        }
+
+        // The D-pad is digital, so it always runs at full speed
+        input.move_axis = if stick_x != 0.0 {
+            stick_x
+        } else {
+            let left = input.held(Action::Left) as i32 as f64;
+            let right = input.held(Action::Right) as i32 as f64;
+            right - left
+        };
+
+        input
+    }
+}
+
+// Read every connected pad through the browser Gamepad API
+pub fn poll_gamepads() -> Vec<GamepadSnapshot> {
+    let Some(window) = web_sys::window() else {
+        return Vec::new();
+    };
+    let Ok(pads) = window.navigator().get_gamepads() else {
+        return Vec::new();
+    };
+
+    pads.iter()
+        .filter_map(|pad| pad.dyn_into::<web_sys::Gamepad>().ok())
+        .filter(|pad| pad.connected())
+        .map(|pad| GamepadSnapshot {
+            axes: pad.axes().iter().map(|axis| axis.as_f64().unwrap_or(0.0)).collect(),
+            buttons: pad
+                .buttons()
+                .iter()
+                .map(|button| {
+                    button
+                        .dyn_into::<web_sys::GamepadButton>()
+                        .map(|button| button.pressed())
+                        .unwrap_or(false)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(axes: &[f64], pressed: &[usize]) -> GamepadSnapshot {
+        let mut buttons = vec![false; 17];
+        for &button in pressed {
+            buttons[button] = true;
+        }
+        GamepadSnapshot { axes: axes.to_vec(), buttons }
+    }
+
+    #[test]
+    fn stick_inside_dead_zone_is_ignored() {
+        let mapping = GamepadMapping::default();
+        let input = mapping.map(&snapshot(&[0.2, -0.2], &[]));
+        assert_eq!(input, GamepadInput::default());
+    }
+
+    #[test]
+    fn stick_scales_run_speed_past_dead_zone() {
+        let mapping = GamepadMapping::default();
+
+        let half = mapping.map(&snapshot(&[-0.625, 0.0], &[]));
+        assert!((half.move_axis + 0.5).abs() < 1e-9);
+        assert!(half.held(Action::Left));
+
+        let full = mapping.map(&snapshot(&[1.0, 0.0], &[]));
+        assert_eq!(full.move_axis, 1.0);
+        assert!(full.held(Action::Right));
+    }
+
+    #[test]
+    fn buttons_and_dpad_map_to_actions() {
+        let mapping = GamepadMapping::default();
+        let input = mapping.map(&snapshot(&[0.0, 0.0], &[0, 2, 5, 14]));
+
+        assert!(input.held(Action::Jump));
+        assert!(input.held(Action::Attack));
+        assert!(input.held(Action::Block));
+        assert!(input.held(Action::Left));
+        assert_eq!(input.move_axis, -1.0);
+        assert!(!input.held(Action::Dash));
+    }
+
+    #[test]
+    fn short_snapshots_and_merging() {
+        let mapping = GamepadMapping::default();
+        // Non-standard pads may report fewer buttons and axes
+        let mut merged = mapping.map(&GamepadSnapshot { axes: vec![0.5], buttons: vec![true] });
+        merged.merge(&mapping.map(&snapshot(&[-0.9, 0.9], &[])));
+
+        assert!(merged.held(Action::Jump));
+        assert!(merged.held(Action::Down));
+        assert!(merged.move_axis < -0.8);
+    }
+}
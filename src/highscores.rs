@@ -0,0 +1,205 @@
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "highScores";
+pub const STANDARD_MODE: &str = "standard";
+pub const LIVES_MODE: &str = "lives";
+pub const DAILY_MODE: &str = "daily";
+pub const MAX_ENTRIES: usize = 10;  // Per board
+const MAX_TOTAL_ENTRIES: usize = 100;  // Past daily boards make room beyond this
+const MAX_HISTORY: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: u64,
+    pub distance: u32,  // Meters
+    pub date: String,  // YYYY-MM-DD
+    pub seed: u32,
+    pub mode: String,
+    #[serde(default = "default_difficulty")]
+    pub difficulty: String,  // Preset the run was played on
+    #[serde(default)]
+    pub assists: Vec<String>,  // Assists used at any point in the run
+}
+
+// Standard and lives runs get a fresh seed each time and share one board per
+// mode. Daily runs share the day's seed, and each day gets its own board.
+fn board_seed(entry: &HighScoreEntry) -> Option<u32> {
+    (entry.mode == DAILY_MODE).then_some(entry.seed)
+}
+
+// Runs saved before difficulty presets were all on the standard one
+fn default_difficulty() -> String {
+    "knight".to_string()
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    entries: Vec<HighScoreEntry>,
+    #[serde(default)]
+    history: Vec<HighScoreEntry>,  // Most recent runs first
+}
+
+impl HighScoreTable {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .get(STORAGE_KEY)
+            .and_then(|json| Self::from_json(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set(STORAGE_KEY, &self.to_json());
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid high score table: {}", e))
+    }
+
+    // Best runs for a mode, optionally limited to one seed, highest first
+    pub fn board(&self, mode: &str, seed: Option<u32>) -> Vec<&HighScoreEntry> {
+        let mut board: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.mode == mode && seed.is_none_or(|seed| entry.seed == seed))
+            .collect();
+        board.sort_by(|a, b| b.score.cmp(&a.score).then(b.distance.cmp(&a.distance)));
+        board
+    }
+
+    pub fn history(&self) -> &[HighScoreEntry] {
+        &self.history
+    }
+
+    // Records a finished run. Returns its 1-based rank on its board if it
+    // made the top MAX_ENTRIES.
+    pub fn submit(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        self.history.insert(0, entry.clone());
+        self.history.truncate(MAX_HISTORY);
+
+        let (mode, seed) = (entry.mode.clone(), board_seed(&entry));
+        self.entries.push(entry.clone());
+
+        // Trim the board back down, dropping its lowest scores
+        let board: Vec<HighScoreEntry> = self.board(&mode, seed).into_iter().cloned().collect();
+        let rank = board.iter().position(|kept| *kept == entry);
+        self.entries.retain(|kept| kept.mode != mode || board_seed(kept) != seed);
+        self.entries.extend(board.into_iter().take(MAX_ENTRIES));
+
+        // Past days' boards go, oldest first, once the table is full
+        while self.entries.len() > MAX_TOTAL_ENTRIES {
+            let oldest = self
+                .entries
+                .iter()
+                .filter(|kept| kept.mode == DAILY_MODE && board_seed(kept) != seed)
+                .min_by(|a, b| a.date.cmp(&b.date))
+                .map(|kept| kept.seed);
+            let Some(oldest) = oldest else { break };
+            self.entries.retain(|kept| kept.mode != DAILY_MODE || kept.seed != oldest);
+        }
+
+        rank.filter(|rank| *rank < MAX_ENTRIES).map(|rank| rank + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn run(score: u64, seed: u32, mode: &str) -> HighScoreEntry {
+        HighScoreEntry {
+            name: "Knight".to_string(),
+            score,
+            distance: score as u32 / 10,
+            date: "2026-01-01".to_string(),
+            seed,
+            mode: mode.to_string(),
+            difficulty: "knight".to_string(),
+            assists: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn runs_on_any_seed_share_their_modes_board() {
+        let mut table = HighScoreTable::default();
+        assert_eq!(table.submit(run(500, 1, "standard")), Some(1));
+        assert_eq!(table.submit(run(800, 2, "standard")), Some(1));
+        assert_eq!(table.submit(run(100, 3, "standard")), Some(3));
+        assert_eq!(table.submit(run(50, 1, "lives")), Some(1));
+
+        let scores: Vec<u64> = table.board("standard", None).iter().map(|e| e.score).collect();
+        assert_eq!(scores, vec![800, 500, 100]);
+        assert_eq!(table.board("lives", None).len(), 1);
+
+        // A fresh seed every run doesn't grow the table past a full board
+        for seed in 10..40 {
+            table.submit(run(seed as u64, seed, "standard"));
+        }
+        assert_eq!(table.submit(run(10, 99, "standard")), None);
+        assert_eq!(table.board("standard", None)[3].score, 39);
+        assert_eq!(table.entries.len(), MAX_ENTRIES + 1);
+    }
+
+    #[test]
+    fn daily_boards_are_per_day_and_old_days_make_room() {
+        let mut table = HighScoreTable::default();
+        assert_eq!(table.submit(run(500, 1, DAILY_MODE)), Some(1));
+        assert_eq!(table.submit(run(100, 2, DAILY_MODE)), Some(1));
+        assert_eq!(table.board(DAILY_MODE, Some(1)).len(), 1);
+
+        // A month of full daily boards stays within the cap, keeping the
+        // latest days
+        for day in 0..30 {
+            for score in 0..MAX_ENTRIES as u64 {
+                let date = format!("2026-02-{:02}", day + 1);
+                table.submit(HighScoreEntry { date, ..run(score, 100 + day, DAILY_MODE) });
+            }
+        }
+        assert!(table.entries.len() <= MAX_TOTAL_ENTRIES);
+        assert!(table.board(DAILY_MODE, Some(1)).is_empty());
+        assert!(table.board(DAILY_MODE, Some(100)).is_empty());
+        assert_eq!(table.board(DAILY_MODE, Some(129)).len(), MAX_ENTRIES);
+    }
+
+    #[test]
+    fn only_top_entries_survive() {
+        let mut table = HighScoreTable::default();
+        for score in 1..=MAX_ENTRIES as u64 {
+            table.submit(run(score * 100, 7, "standard"));
+        }
+        assert_eq!(table.submit(run(50, 7, "standard")), None);
+        assert_eq!(table.submit(run(550, 7, "standard")), Some(6));
+
+        let board = table.board("standard", None);
+        assert_eq!(board.len(), MAX_ENTRIES);
+        assert_eq!(board.last().unwrap().score, 200);
+        assert_eq!(table.history().len(), MAX_ENTRIES + 2);
+        assert_eq!(table.history()[0].score, 550);
+    }
+
+    #[test]
+    fn round_trips_through_storage() {
+        let mut storage = MemoryStorage::default();
+        assert_eq!(HighScoreTable::load(&storage), HighScoreTable::default());
+
+        let mut table = HighScoreTable::default();
+        table.submit(run(300, 3, "standard"));
+        table.save(&mut storage);
+        assert_eq!(HighScoreTable::load(&storage), table);
+
+        // Entries from before difficulty presets load as knight runs
+        storage.set("highScores", "{\"entries\": [{\"name\": \"Old\", \"score\": 10, \"distance\": 1, \"date\": \"2025-01-01\", \"seed\": 1, \"mode\": \"standard\"}]}");
+        let old = HighScoreTable::load(&storage);
+        assert_eq!(old.board("standard", None)[0].difficulty, "knight");
+        assert!(old.board("standard", None)[0].assists.is_empty());
+
+        storage.set("highScores", "not json");
+        assert_eq!(HighScoreTable::load(&storage), HighScoreTable::default());
+    }
+}
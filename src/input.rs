@@ -0,0 +1,368 @@
+use crate::gamepad::GamepadInput;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Action {
+    Left,
+    Right,
+    Jump,
+    Down,
+    Attack,
+    Block,
+    Dash,
+    Pause,
+    Confirm,
+    UseItem,
+    CycleItem,
+}
+
+pub const ACTION_COUNT: usize = 11;
+
+impl Action {
+    pub const ALL: [Action; ACTION_COUNT] = [
+        Action::Left,
+        Action::Right,
+        Action::Jump,
+        Action::Down,
+        Action::Attack,
+        Action::Block,
+        Action::Dash,
+        Action::Pause,
+        Action::Confirm,
+        Action::UseItem,
+        Action::CycleItem,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Left => "Left",
+            Action::Right => "Right",
+            Action::Jump => "Jump",
+            Action::Down => "Down",
+            Action::Attack => "Attack",
+            Action::Block => "Block",
+            Action::Dash => "Dash",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::UseItem => "UseItem",
+            Action::CycleItem => "CycleItem",
+        }
+    }
+}
+
+// Short name for a KeyboardEvent.key, for showing bindings on screen
+pub fn key_label(key: &str) -> String {
+    match key {
+        " " => "Space".to_string(),
+        "ArrowLeft" => "Left".to_string(),
+        "ArrowRight" => "Right".to_string(),
+        "ArrowUp" => "Up".to_string(),
+        "ArrowDown" => "Down".to_string(),
+        "Escape" => "Esc".to_string(),
+        _ => key.to_string(),
+    }
+}
+
+// Single character keys are matched case-insensitively so "a" covers "A"
+fn normalize_key(key: &str) -> String {
+    if key.chars().count() == 1 {
+        key.to_lowercase()
+    } else {
+        key.to_string()
+    }
+}
+
+// Maps KeyboardEvent.key strings to actions
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyMap {
+    bindings: BTreeMap<Action, Vec<String>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut key_map = KeyMap { bindings: BTreeMap::new() };
+        for (action, keys) in [
+            (Action::Left, &["ArrowLeft", "a"][..]),
+            (Action::Right, &["ArrowRight", "d"][..]),
+            (Action::Jump, &["ArrowUp", "w"][..]),
+            (Action::Down, &["ArrowDown", "s"][..]),
+            (Action::Attack, &[" "][..]),
+            (Action::Block, &["Shift"][..]),
+            (Action::Dash, &["c"][..]),
+            (Action::Pause, &["Escape", "p"][..]),
+            (Action::Confirm, &["Enter"][..]),
+            (Action::UseItem, &["e"][..]),
+            (Action::CycleItem, &["q"][..]),
+        ] {
+            for key in keys {
+                key_map.bind(action, key);
+            }
+        }
+        key_map
+    }
+}
+
+impl KeyMap {
+    pub fn action_for(&self, key: &str) -> Option<Action> {
+        let key = normalize_key(key);
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    // Adds a binding, taking the key away from any other action
+    pub fn bind(&mut self, action: Action, key: &str) {
+        let key = normalize_key(key);
+        for keys in self.bindings.values_mut() {
+            keys.retain(|bound| *bound != key);
+        }
+        self.bindings.entry(action).or_default().push(key);
+    }
+
+    pub fn keys_for(&self, action: Action) -> &[String] {
+        self.bindings.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // Replaces every binding of an action with a single key
+    pub fn rebind(&mut self, action: Action, key: &str) {
+        self.bindings.remove(&action);
+        self.bind(action, key);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> Result<KeyMap, String> {
+        let mut key_map: KeyMap =
+            serde_json::from_str(json).map_err(|e| format!("Invalid key map: {}", e))?;
+
+        // Maps saved before an action existed get its default keys
+        let defaults = KeyMap::default();
+        for (action, keys) in defaults.bindings {
+            if !key_map.bindings.contains_key(&action) {
+                for key in keys {
+                    if key_map.action_for(&key).is_none() {
+                        key_map.bind(action, &key);
+                    }
+                }
+            }
+        }
+        Ok(key_map)
+    }
+}
+
+// Action state for a single simulation tick
+#[derive(Clone, Default)]
+pub struct KeyState {
+    held: [bool; ACTION_COUNT],
+    pressed: [bool; ACTION_COUNT],
+    released: [bool; ACTION_COUNT],
+    move_axis: f64,
+}
+
+impl KeyState {
+    pub fn held(&self, action: Action) -> bool {
+        self.held[action.index()]
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed[action.index()]
+    }
+
+    pub fn released(&self, action: Action) -> bool {
+        self.released[action.index()]
+    }
+
+    // Horizontal run input from -1.0 (full left) to 1.0 (full right)
+    pub fn move_axis(&self) -> f64 {
+        self.move_axis
+    }
+}
+
+// Collects raw key events, gamepad and touch state between ticks and turns
+// them into per-tick edges
+#[derive(Default)]
+pub struct Input {
+    pub key_map: KeyMap,
+    keys_down: Vec<String>,
+    tapped: [bool; ACTION_COUNT],  // Pressed and released again before the tick ran
+    gamepad: GamepadInput,
+    touch_held: [bool; ACTION_COUNT],
+    state: KeyState,
+    listening: bool,  // The next key press is captured for rebinding
+    captured: Option<String>,
+}
+
+impl Input {
+    // Returns true if the key is bound to an action, or was captured
+    pub fn key_down(&mut self, key: &str) -> bool {
+        if self.listening {
+            self.listening = false;
+            self.captured = Some(key.to_string());
+            return true;
+        }
+        let Some(action) = self.key_map.action_for(key) else {
+            return false;
+        };
+        let key = normalize_key(key);
+        if !self.keys_down.contains(&key) {
+            self.keys_down.push(key);
+        }
+        self.tapped[action.index()] = false;
+        true
+    }
+
+    pub fn key_up(&mut self, key: &str) -> bool {
+        let Some(action) = self.key_map.action_for(key) else {
+            return false;
+        };
+        let key = normalize_key(key);
+        // A captured key was never down, so releasing it shouldn't tap its new action
+        let was_down = self.keys_down.contains(&key);
+        self.keys_down.retain(|down| *down != key);
+        if was_down && !self.state.held(action) && !self.action_down(action) {
+            self.tapped[action.index()] = true;
+        }
+        true
+    }
+
+    fn action_down(&self, action: Action) -> bool {
+        self.keys_down
+            .iter()
+            .any(|key| self.key_map.action_for(key) == Some(action))
+    }
+
+    // Advance to the next tick, computing edges against the previous one
+    pub fn tick(&mut self) -> &KeyState {
+        for action in Action::ALL {
+            let i = action.index();
+            let was_held = self.state.held[i];
+            let held = self.action_down(action) || self.gamepad.held(action) || self.touch_held[i];
+            let tapped = std::mem::take(&mut self.tapped[i]);
+
+            self.state.held[i] = held;
+            self.state.pressed[i] = (held && !was_held) || tapped;
+            self.state.released[i] = (!held && was_held) || tapped;
+        }
+
+        self.state.move_axis = if self.gamepad.move_axis != 0.0 {
+            self.gamepad.move_axis
+        } else {
+            self.state.held(Action::Right) as i32 as f64 - self.state.held(Action::Left) as i32 as f64
+        };
+        &self.state
+    }
+
+    pub fn set_gamepad(&mut self, gamepad: GamepadInput) {
+        self.gamepad = gamepad;
+    }
+
+    pub fn set_touch_held(&mut self, held: [bool; ACTION_COUNT]) {
+        for (i, now_held) in held.iter().enumerate() {
+            // Quick taps can begin and end between two ticks
+            if self.touch_held[i] && !now_held && !self.state.held[i] {
+                self.tapped[i] = true;
+            }
+        }
+        self.touch_held = held;
+    }
+
+    pub fn set_key_map(&mut self, key_map: KeyMap) {
+        self.key_map = key_map;
+        self.keys_down.clear();
+    }
+
+    // Starts waiting for a key to rebind; it is returned by take_captured
+    pub fn listen(&mut self) {
+        self.listening = true;
+        self.captured = None;
+    }
+
+    pub fn take_captured(&mut self) -> Option<String> {
+        self.captured.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges_last_a_single_tick() {
+        let mut input = Input::default();
+        input.key_down("ArrowUp");
+        let state = input.tick().clone();
+        assert!(state.pressed(Action::Jump) && state.held(Action::Jump));
+
+        let state = input.tick().clone();
+        assert!(!state.pressed(Action::Jump) && state.held(Action::Jump));
+
+        input.key_up("ArrowUp");
+        let state = input.tick().clone();
+        assert!(state.released(Action::Jump) && !state.held(Action::Jump));
+    }
+
+    #[test]
+    fn taps_between_ticks_are_not_lost() {
+        let mut input = Input::default();
+        input.key_down(" ");
+        input.key_up(" ");
+        let state = input.tick();
+        assert!(state.pressed(Action::Attack));
+        assert!(!state.held(Action::Attack));
+    }
+
+    #[test]
+    fn action_stays_held_while_any_bound_key_is_down() {
+        let mut input = Input::default();
+        input.key_down("a");
+        input.key_down("ArrowLeft");
+        input.tick();
+        input.key_up("A");
+        assert!(input.tick().held(Action::Left));
+    }
+
+    #[test]
+    fn rebinding_moves_keys_and_round_trips_through_json() {
+        let mut key_map = KeyMap::default();
+        key_map.rebind(Action::Attack, "J");
+        key_map.bind(Action::Jump, " ");
+
+        assert_eq!(key_map.action_for("j"), Some(Action::Attack));
+        assert_eq!(key_map.action_for(" "), Some(Action::Jump));
+        assert_eq!(key_map.action_for("Space"), None);
+
+        let restored = KeyMap::from_json(&key_map.to_json()).unwrap();
+        assert_eq!(restored, key_map);
+        assert!(KeyMap::from_json("{\"bindings\": 3}").is_err());
+
+        let old = KeyMap::from_json("{\"bindings\": {\"Jump\": [\"p\"]}}").unwrap();
+        assert_eq!(old.action_for("p"), Some(Action::Jump));
+        assert_eq!(old.action_for("Escape"), Some(Action::Pause));
+        assert_eq!(old.action_for("ArrowUp"), None);
+    }
+
+    #[test]
+    fn captured_keys_do_not_trigger_actions() {
+        let mut input = Input::default();
+        input.listen();
+        assert!(input.key_down("x"));
+        let key = input.take_captured().unwrap();
+        input.key_map.rebind(Action::Jump, &key);
+        input.key_up("x");
+        let state = input.tick();
+        assert!(!state.pressed(Action::Jump) && !state.held(Action::Jump));
+        assert_eq!(input.take_captured(), None);
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+pub const SLOT_COUNT: usize = 3;
+pub const MAX_STACK: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ItemKind {
+    Potion,  // Restores two hearts
+    Dagger,  // Thrown straight ahead
+    Bomb,    // Lobbed in an arc, explodes on landing
+}
+
+impl ItemKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemKind::Potion => "POTION",
+            ItemKind::Dagger => "THROWING DAGGER",
+            ItemKind::Bomb => "BOMB",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: [Option<ItemStack>; SLOT_COUNT],
+    selected: usize,
+}
+
+impl Inventory {
+    // Stacks onto a matching slot first, then the first empty one. Returns
+    // false when there is no room.
+    pub fn add(&mut self, kind: ItemKind) -> bool {
+        if let Some(stack) = self
+            .slots
+            .iter_mut()
+            .flatten()
+            .find(|stack| stack.kind == kind && stack.count < MAX_STACK)
+        {
+            stack.count += 1;
+            return true;
+        }
+        match self.slots.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(ItemStack { kind, count: 1 });
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cycle(&mut self) {
+        self.selected = (self.selected + 1) % SLOT_COUNT;
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn selected_item(&self) -> Option<ItemKind> {
+        self.slots[self.selected].map(|stack| stack.kind)
+    }
+
+    // Removes one of the selected item, emptying the slot with the last one
+    pub fn take_selected(&mut self) -> Option<ItemKind> {
+        let slot = &mut self.slots[self.selected];
+        let stack = slot.as_mut()?;
+        let kind = stack.kind;
+        stack.count -= 1;
+        if stack.count == 0 {
+            *slot = None;
+        }
+        Some(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_stack_then_fill_empty_slots() {
+        let mut inventory = Inventory::default();
+        for _ in 0..MAX_STACK + 1 {
+            assert!(inventory.add(ItemKind::Potion));
+        }
+        assert!(inventory.add(ItemKind::Bomb));
+        assert_eq!(inventory.slots()[0], Some(ItemStack { kind: ItemKind::Potion, count: MAX_STACK }));
+        assert_eq!(inventory.slots()[1], Some(ItemStack { kind: ItemKind::Potion, count: 1 }));
+        assert!(!inventory.add(ItemKind::Dagger));
+        assert!(inventory.add(ItemKind::Bomb));
+    }
+
+    #[test]
+    fn using_the_last_item_frees_its_slot() {
+        let mut inventory = Inventory::default();
+        inventory.add(ItemKind::Dagger);
+        inventory.add(ItemKind::Bomb);
+
+        inventory.cycle();
+        assert_eq!(inventory.take_selected(), Some(ItemKind::Bomb));
+        assert_eq!(inventory.take_selected(), None);
+        assert!(inventory.slots()[1].is_none());
+
+        inventory.cycle();
+        inventory.cycle();
+        assert_eq!(inventory.selected(), 0);
+        assert_eq!(inventory.selected_item(), Some(ItemKind::Dagger));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::Player;
+use serde::{Deserialize, Serialize};
+
+// XP for the first level-up; each later one needs XP_GROWTH times more
+const XP_BASE: f64 = 30.0;
+const XP_GROWTH: f64 = 1.4;
+
+// XP needed to go from `level` to the next one
+pub fn xp_to_next(level: u32) -> u32 {
+    (XP_BASE * XP_GROWTH.powi(level as i32 - 1)).round() as u32
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: u32,
+    pub xp: u32,  // Progress towards the next level
+    pub pending: u32,  // Level-ups still waiting for a stat choice
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Experience { level: 1, xp: 0, pending: 0 }
+    }
+}
+
+impl Experience {
+    // Returns the number of levels gained
+    pub fn gain(&mut self, xp: u32) -> u32 {
+        self.xp += xp;
+        let mut gained = 0;
+        while self.xp >= xp_to_next(self.level) {
+            self.xp -= xp_to_next(self.level);
+            self.level += 1;
+            gained += 1;
+        }
+        self.pending += gained;
+        gained
+    }
+
+    // 0.0..1.0 towards the next level, for the HUD bar
+    pub fn progress(&self) -> f64 {
+        self.xp as f64 / xp_to_next(self.level) as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatChoice {
+    AttackDamage,
+    MaxHealth,
+    Speed,
+    JumpPower,
+}
+
+impl StatChoice {
+    pub const ALL: [StatChoice; 4] = [
+        StatChoice::AttackDamage,
+        StatChoice::MaxHealth,
+        StatChoice::Speed,
+        StatChoice::JumpPower,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StatChoice::AttackDamage => "Sharper Blade",
+            StatChoice::MaxHealth => "Stout Heart",
+            StatChoice::Speed => "Fleet Foot",
+            StatChoice::JumpPower => "Spring Step",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            StatChoice::AttackDamage => "+1 sword damage",
+            StatChoice::MaxHealth => "+1 max heart, fully healed",
+            StatChoice::Speed => "+10% run speed",
+            StatChoice::JumpPower => "+6% jump height",
+        }
+    }
+
+    pub fn apply(self, player: &mut Player) {
+        match self {
+            StatChoice::AttackDamage => player.attack_bonus += 1,
+            StatChoice::MaxHealth => {
+                player.raise_max_health(4.0);
+                player.health = player.max_health;
+            }
+            StatChoice::Speed => player.speed += 0.5,
+            StatChoice::JumpPower => player.jump_power += 0.75,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_curve_grows_and_carries_overflow() {
+        assert_eq!(xp_to_next(1), 30);
+        assert_eq!(xp_to_next(2), 42);
+        assert_eq!(xp_to_next(3), 59);
+
+        let mut experience = Experience::default();
+        assert_eq!(experience.gain(29), 0);
+        assert_eq!(experience.gain(45), 2);
+        assert_eq!(experience.level, 3);
+        assert_eq!(experience.xp, 2);
+        assert_eq!(experience.pending, 2);
+    }
+
+    #[test]
+    fn stat_choices_feed_player_fields() {
+        let mut player = Player::new();
+        player.health = 10.0;
+        for choice in StatChoice::ALL {
+            choice.apply(&mut player);
+        }
+        assert_eq!(player.attack_bonus, 1);
+        assert_eq!(player.sword_damage(), 2);
+        assert_eq!(player.max_health, 32.0);
+        assert_eq!(player.health, 32.0);
+        assert_eq!(player.speed, 5.5);
+        assert_eq!(player.jump_power, 12.75);
+    }
+}
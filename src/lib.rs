@@ -1,15 +1,68 @@
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, KeyboardEvent};
+use web_sys::CanvasRenderingContext2d;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+mod achievements;
+mod audio;
+mod daily;
+mod difficulty;
+mod effects;
+mod events;
+mod font;
+mod gamepad;
+mod highscores;
+mod input;
+mod inventory;
+mod leveling;
+mod music;
+mod particles;
+mod save;
+mod scene;
+mod score;
+mod settings;
+mod sfx;
+mod shop;
+mod storage;
+mod touch;
+mod ui;
+
+use achievements::Achievements;
+use audio::{AudioPlayer, Tune};
+use daily::{DailyChallenge, Modifiers, DAILY_DIFFICULTY};
+use difficulty::{Assists, Difficulty, SLOW_MOTION_SPEED};
+use effects::{ActiveEffects, EffectKind, PickupKind};
+use events::GameEvent;
+use gamepad::{GamepadInput, GamepadMapping};
+use highscores::{HighScoreEntry, HighScoreTable, DAILY_MODE, LIVES_MODE, STANDARD_MODE};
+use input::{Action, Input, KeyMap, KeyState};
+use inventory::{Inventory, ItemKind};
+use leveling::{Experience, StatChoice};
+use music::Track;
+use particles::Particles;
+use scene::{Scene, GAME_OVER_DELAY};
+use score::{RunSummary, ScoreTracker};
+use settings::{OptionItem, Settings, VOLUME_STEPS};
+use sfx::Sfx;
+use shop::{Progress, Upgrades};
+use storage::{LocalStorage, MemoryStorage, Storage};
+use touch::TouchControls;
+use ui::{Anchor, Nav, Rect, Ui};
+
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+// Native builds (unit tests) have no console to log to
+#[cfg(not(target_arch = "wasm32"))]
+fn log(s: &str) {
+    println!("{}", s);
+}
+
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
@@ -23,8 +76,8 @@ impl PixelSprite {
         let pixel_size = scale;
         for (row_idx, row) in self.pixels.iter().enumerate() {
             for (col_idx, color) in row.iter().enumerate() {
-                if *color != "" {
-                    ctx.set_fill_style(&JsValue::from_str(color));
+                if !color.is_empty() {
+                    ctx.set_fill_style_str(color);
                     let px = if flip_h {
                         x + (self.pixels[0].len() as f64 - col_idx as f64 - 1.0) * pixel_size
                     } else {
@@ -67,13 +120,49 @@ fn create_knight_sprite(frame: usize) -> PixelSprite {
         vec!["#2E3440", "#2E3440", "", "", "", "#2E3440", "#2E3440", ""],
     ];
     
-    if frame % 2 == 0 {
+    if frame.is_multiple_of(2) {
         PixelSprite { pixels: idle }
     } else {
         PixelSprite { pixels: walk1 }
     }
 }
 
+fn create_knight_block_sprite(parrying: bool) -> PixelSprite {
+    // Shield raised in front of the body; it flashes gold during the parry window
+    let rim = if parrying { "#FFD700" } else { "#C0C0C0" };
+    let face = if parrying { "#FFFACD" } else { "#8B4513" };
+    PixelSprite {
+        pixels: vec![
+            vec!["", "", "", "#C0C0C0", "#C0C0C0", "", "", ""],
+            vec!["", "", "#C0C0C0", "#C0C0C0", "#C0C0C0", "#C0C0C0", "", ""],
+            vec!["", "", "#FDBCB4", "#FDBCB4", "#FDBCB4", "#FDBCB4", "", ""],
+            vec!["", "", "#000000", "#FDBCB4", "#FDBCB4", "#000000", rim, ""],
+            vec!["", "", "#FDBCB4", "#FDBCB4", "#FDBCB4", rim, face, rim],
+            vec!["", "#C0C0C0", "#4169E1", "#4169E1", "#4169E1", rim, face, rim],
+            vec!["#C0C0C0", "#4169E1", "#4169E1", "#4169E1", "#4169E1", rim, face, rim],
+            vec!["", "#4169E1", "#4169E1", "#4169E1", "#4169E1", rim, face, rim],
+            vec!["", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#4169E1", rim, ""],
+            vec!["", "#2E3440", "#2E3440", "", "", "#2E3440", "#2E3440", ""],
+            vec!["#2E3440", "#2E3440", "#2E3440", "", "", "#2E3440", "#2E3440", "#2E3440"],
+        ]
+    }
+}
+
+fn create_knight_crouch_sprite() -> PixelSprite {
+    PixelSprite {
+        pixels: vec![
+            vec!["", "", "", "#C0C0C0", "#C0C0C0", "", "", ""],
+            vec!["", "", "#C0C0C0", "#C0C0C0", "#C0C0C0", "#C0C0C0", "", ""],
+            vec!["", "", "#000000", "#FDBCB4", "#FDBCB4", "#000000", "", ""],
+            vec!["", "#C0C0C0", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#C0C0C0", ""],
+            vec!["#C0C0C0", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#C0C0C0"],
+            vec!["", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#4169E1", "#4169E1", ""],
+            vec!["#2E3440", "#2E3440", "#2E3440", "", "", "#2E3440", "#2E3440", "#2E3440"],
+            vec!["#2E3440", "#2E3440", "#2E3440", "", "", "#2E3440", "#2E3440", "#2E3440"],
+        ]
+    }
+}
+
 fn create_goblin_sprite() -> PixelSprite {
     PixelSprite {
         pixels: vec![
@@ -90,6 +179,22 @@ fn create_goblin_sprite() -> PixelSprite {
     }
 }
 
+fn create_brute_sprite() -> PixelSprite {
+    PixelSprite {
+        pixels: vec![
+            vec!["", "#556B2F", "#556B2F", "#556B2F", "#556B2F", "#556B2F", ""],
+            vec!["#556B2F", "#556B2F", "#556B2F", "#556B2F", "#556B2F", "#556B2F", "#556B2F"],
+            vec!["#556B2F", "#FFA500", "#556B2F", "#556B2F", "#FFA500", "#556B2F", "#556B2F"],
+            vec!["#556B2F", "#FFFFF0", "#556B2F", "#556B2F", "#FFFFF0", "#556B2F", ""],
+            vec!["#696969", "#696969", "#696969", "#696969", "#696969", "#556B2F", "#5C4033"],
+            vec!["#556B2F", "#696969", "#2F4F4F", "#2F4F4F", "#696969", "#556B2F", "#5C4033"],
+            vec!["", "#696969", "#696969", "#696969", "#696969", "#556B2F", "#5C4033"],
+            vec!["", "#556B2F", "#556B2F", "", "#556B2F", "#556B2F", ""],
+            vec!["#556B2F", "#556B2F", "#556B2F", "", "#556B2F", "#556B2F", "#556B2F"],
+        ]
+    }
+}
+
 fn create_sword_sprite_vertical() -> PixelSprite {
     PixelSprite {
         pixels: vec![
@@ -129,16 +234,16 @@ fn draw_pixel_platform(ctx: &CanvasRenderingContext2d, x: f64, y: f64, width: f6
             let py = y + ty as f64 * tile_size;
             
             if ty == 0 {
-                ctx.set_fill_style(&JsValue::from_str("#228B22"));
+                ctx.set_fill_style_str("#228B22");
                 ctx.fill_rect(px, py, tile_size, 2.0);
-                ctx.set_fill_style(&JsValue::from_str("#8B4513"));
+                ctx.set_fill_style_str("#8B4513");
                 ctx.fill_rect(px, py + 2.0, tile_size, tile_size - 2.0);
             } else {
-                ctx.set_fill_style(&JsValue::from_str("#654321"));
+                ctx.set_fill_style_str("#654321");
                 ctx.fill_rect(px, py, tile_size, tile_size);
             }
             
-            ctx.set_stroke_style(&JsValue::from_str("#4A2C17"));
+            ctx.set_stroke_style_str("#4A2C17");
             ctx.set_line_width(0.5);
             ctx.stroke_rect(px, py, tile_size, tile_size);
         }
@@ -146,7 +251,7 @@ fn draw_pixel_platform(ctx: &CanvasRenderingContext2d, x: f64, y: f64, width: f6
 }
 
 fn draw_pixel_heart(ctx: &CanvasRenderingContext2d, x: f64, y: f64, scale: f64) {
-    let heart = vec![
+    let heart = [
         vec!["", "#FF0000", "#FF0000", "", "#FF0000", "#FF0000", ""],
         vec!["#FF0000", "#FF69B4", "#FF69B4", "#FF0000", "#FF69B4", "#FF69B4", "#FF0000"],
         vec!["#FF0000", "#FF69B4", "#FF69B4", "#FF69B4", "#FF69B4", "#FF69B4", "#FF0000"],
@@ -157,8 +262,8 @@ fn draw_pixel_heart(ctx: &CanvasRenderingContext2d, x: f64, y: f64, scale: f64)
     
     for (row_idx, row) in heart.iter().enumerate() {
         for (col_idx, color) in row.iter().enumerate() {
-            if *color != "" {
-                ctx.set_fill_style(&JsValue::from_str(color));
+            if !color.is_empty() {
+                ctx.set_fill_style_str(color);
                 ctx.fill_rect(
                     x + col_idx as f64 * scale,
                     y + row_idx as f64 * scale,
@@ -170,10 +275,89 @@ fn draw_pixel_heart(ctx: &CanvasRenderingContext2d, x: f64, y: f64, scale: f64)
     }
 }
 
+// Spinning gold coin; the width narrows and widens over time
+fn draw_pixel_coin(ctx: &CanvasRenderingContext2d, x: f64, y: f64, time: f64) {
+    let size = Coin::SIZE;
+    let width = (size * (time * 0.1).cos().abs()).max(3.0);
+    let left = x + (size - width) / 2.0;
+    ctx.set_fill_style_str("#B8860B");
+    ctx.fill_rect(left, y, width, size);
+    ctx.set_fill_style_str("#FFD700");
+    ctx.fill_rect(left + 1.5, y + 1.5, (width - 3.0).max(0.0), size - 3.0);
+    ctx.set_fill_style_str("#FFFACD");
+    ctx.fill_rect(left + 1.5, y + 1.5, (width / 4.0).max(1.0), 3.0);
+}
+
+// Framed gem showing the effect's icon letter
+fn draw_power_up(ctx: &CanvasRenderingContext2d, x: f64, y: f64, effect: EffectKind) {
+    let (letter, color) = effect.icon();
+    ctx.set_fill_style_str("#3B2F2F");
+    ctx.fill_rect(x + 1.0, y + 1.0, 18.0, 18.0);
+    ctx.set_fill_style_str(color);
+    ctx.fill_rect(x + 3.0, y + 3.0, 14.0, 14.0);
+    ctx.set_fill_style_str("#FFFFFF");
+    ctx.fill_rect(x + 4.0, y + 4.0, 3.0, 3.0);
+    font::draw_text(ctx, letter, x + 8.0, y + 7.0, 1.0, "#000000");
+}
+
+// 20x20 pixel icon for an inventory item
+fn draw_item_icon(ctx: &CanvasRenderingContext2d, x: f64, y: f64, item: ItemKind) {
+    match item {
+        ItemKind::Potion => {
+            ctx.set_fill_style_str("#8B4513");
+            ctx.fill_rect(x + 8.0, y + 1.0, 4.0, 3.0);
+            ctx.set_fill_style_str("#ADD8E6");
+            ctx.fill_rect(x + 7.0, y + 4.0, 6.0, 4.0);
+            ctx.fill_rect(x + 3.0, y + 8.0, 14.0, 11.0);
+            ctx.set_fill_style_str("#DC143C");
+            ctx.fill_rect(x + 4.0, y + 11.0, 12.0, 7.0);
+            ctx.set_fill_style_str("#FFFFFF");
+            ctx.fill_rect(x + 5.0, y + 9.0, 2.0, 2.0);
+        }
+        ItemKind::Dagger => {
+            ctx.set_fill_style_str("#C0C0C0");
+            ctx.fill_rect(x + 9.0, y + 1.0, 3.0, 11.0);
+            ctx.set_fill_style_str("#FFFFFF");
+            ctx.fill_rect(x + 9.0, y + 1.0, 1.0, 10.0);
+            ctx.set_fill_style_str("#DAA520");
+            ctx.fill_rect(x + 5.0, y + 12.0, 11.0, 2.0);
+            ctx.set_fill_style_str("#8B4513");
+            ctx.fill_rect(x + 9.0, y + 14.0, 3.0, 5.0);
+        }
+        ItemKind::Bomb => {
+            ctx.set_fill_style_str("#222222");
+            ctx.begin_path();
+            ctx.arc(x + 10.0, y + 12.0, 7.0, 0.0, std::f64::consts::PI * 2.0).unwrap();
+            ctx.fill();
+            ctx.set_fill_style_str("#666666");
+            ctx.fill_rect(x + 6.0, y + 8.0, 2.0, 2.0);
+            ctx.set_fill_style_str("#8B4513");
+            ctx.fill_rect(x + 10.0, y + 2.0, 2.0, 4.0);
+            ctx.set_fill_style_str("#FF4500");
+            ctx.fill_rect(x + 11.0, y, 3.0, 3.0);
+        }
+    }
+}
+
+// Pole with a pennant that is raised and turns gold once reached
+fn draw_checkpoint_banner(ctx: &CanvasRenderingContext2d, x: f64, y: f64, reached: bool) {
+    ctx.set_fill_style_str("#5C4033");
+    ctx.fill_rect(x, y - 48.0, 3.0, 48.0);
+    ctx.fill_rect(x - 3.0, y - 3.0, 9.0, 3.0);
+
+    let flag_y = if reached { y - 48.0 } else { y - 24.0 };
+    let (flag, trim) = if reached { ("#FFD700", "#DAA520") } else { ("#8B0000", "#5A0000") };
+    ctx.set_fill_style_str(flag);
+    ctx.fill_rect(x + 3.0, flag_y, 15.0, 12.0);
+    ctx.set_fill_style_str(trim);
+    ctx.fill_rect(x + 3.0, flag_y + 9.0, 15.0, 3.0);
+    ctx.fill_rect(x + 15.0, flag_y + 3.0, 3.0, 6.0);
+}
+
 fn draw_heart_quarters(ctx: &CanvasRenderingContext2d, x: f64, y: f64, quarters: i32) {
     if quarters <= 0 {
         // Empty heart - just outline
-        ctx.set_stroke_style(&JsValue::from_str("#800000"));
+        ctx.set_stroke_style_str("#800000");
         ctx.set_line_width(1.5);
         ctx.stroke_rect(x, y, 21.0, 18.0);
         return;
@@ -186,11 +370,11 @@ fn draw_heart_quarters(ctx: &CanvasRenderingContext2d, x: f64, y: f64, quarters:
     }
     
     // Partial hearts - draw outline then fill partially
-    ctx.set_stroke_style(&JsValue::from_str("#800000"));
+    ctx.set_stroke_style_str("#800000");
     ctx.set_line_width(1.5);
     ctx.stroke_rect(x, y, 21.0, 18.0);
     
-    ctx.set_fill_style(&JsValue::from_str("#FF0000"));
+    ctx.set_fill_style_str("#FF0000");
     match quarters {
         1 => ctx.fill_rect(x + 1.0, y + 1.0, 5.0, 16.0),
         2 => ctx.fill_rect(x + 1.0, y + 1.0, 10.0, 16.0),
@@ -199,7 +383,7 @@ fn draw_heart_quarters(ctx: &CanvasRenderingContext2d, x: f64, y: f64, quarters:
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Player {
     x: f64,
     y: f64,
@@ -213,13 +397,175 @@ struct Player {
     facing_right: bool,
     is_attacking: bool,
     attack_cooldown: f64,
+    attack_duration: f64,  // Seconds per sword swing
+    attack_bonus: i32,  // Extra sword damage from level-ups
     health: f64,  // Changed to f64 for quarter hearts
     max_health: f64,
     animation_frame: f64,
-    sword_angle: f64,
     damage_cooldown: f64,
+    iframe_duration: f64,  // Seconds of invincibility after taking a hit
+    hit_damage: f64,  // Quarter hearts lost per enemy hit
+    poison_tick: f64,  // Seconds since poison last drained a quarter
     invincible: bool,
     is_dead: bool,
+    is_blocking: bool,
+    parry_timer: f64,  // Seconds left in the parry window after raising the shield
+    parry_cooldown: f64,  // Seconds until raising the shield can parry again
+    stamina: f64,
+    max_stamina: f64,
+    effects: ActiveEffects,
+    inventory: Inventory,
+    experience: Experience,
+    abilities: Abilities,
+    movement: MovementConfig,
+    jump_held: bool,
+    jump_buffer: u32,  // Frames left on a buffered jump press
+    air_frames: u32,  // Frames since last standing on the ground
+    has_jumped: bool,  // Left the ground by jumping rather than walking off
+    can_cut_jump: bool,
+    air_jumps_left: u32,
+    #[serde(skip)]
+    jumped: bool,  // Left the ground or air this frame, for the jump sound
+    air_dash_available: bool,
+    input_dir: f64,  // -1.0 / 1.0 while a direction is held this frame
+    wall_contact: Option<WallSide>,
+    is_wall_sliding: bool,
+    wall_jump_lock: f64,  // Seconds horizontal input is ignored after a wall jump
+    dash_timer: f64,
+    dash_cooldown: f64,
+    is_crouching: bool,
+    on_one_way: bool,  // Standing on a thin platform that can be dropped through
+    drop_through_timer: f64,
+}
+
+const STAND_HEIGHT: f64 = 32.0;
+const CROUCH_HEIGHT: f64 = 24.0;
+const ONE_WAY_MAX_HEIGHT: f64 = 20.0;
+
+// Movement tuning. Velocities are per physics frame, dash times in seconds.
+#[derive(Clone, Serialize, Deserialize)]
+struct MovementConfig {
+    gravity: f64,
+    max_fall_speed: f64,
+    coyote_frames: u32,  // Airborne frames after leaving a ledge that still allow a jump
+    jump_buffer_frames: u32,  // Frames a jump press is remembered before landing
+    jump_cut_multiplier: f64,  // Applied to upward velocity when jump is released early
+    apex_threshold: f64,  // Vertical speed under which the knight is considered at the apex
+    apex_gravity_multiplier: f64,
+    wall_slide_speed: f64,
+    dash_speed: f64,
+    dash_duration: f64,
+    dash_cooldown: f64,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        MovementConfig {
+            gravity: 0.5,
+            max_fall_speed: 15.0,
+            coyote_frames: 6,
+            jump_buffer_frames: 6,
+            jump_cut_multiplier: 0.5,
+            apex_threshold: 1.5,
+            apex_gravity_multiplier: 0.5,
+            wall_slide_speed: 2.0,
+            dash_speed: 12.0,
+            dash_duration: 0.15,
+            dash_cooldown: 0.8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum WallSide {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum Ability {
+    Crouch,
+    DoubleJump,
+    Dash,
+    WallSlide,
+    WallJump,
+}
+
+impl Ability {
+    fn name(self) -> &'static str {
+        match self {
+            Ability::Crouch => "CROUCH & DROP",
+            Ability::DoubleJump => "DOUBLE JUMP",
+            Ability::Dash => "AIR DASH",
+            Ability::WallSlide => "WALL SLIDE",
+            Ability::WallJump => "WALL JUMP",
+        }
+    }
+}
+
+// Distance in meters at which each ability is granted during a run
+const ABILITY_UNLOCKS: [(f64, Ability); 5] = [
+    (25.0, Ability::Crouch),
+    (100.0, Ability::DoubleJump),
+    (200.0, Ability::Dash),
+    (350.0, Ability::WallSlide),
+    (500.0, Ability::WallJump),
+];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Abilities {
+    crouch: bool,
+    double_jump: bool,
+    dash: bool,
+    wall_slide: bool,
+    wall_jump: bool,
+}
+
+impl Abilities {
+    fn has(&self, ability: Ability) -> bool {
+        match ability {
+            Ability::Crouch => self.crouch,
+            Ability::DoubleJump => self.double_jump,
+            Ability::Dash => self.dash,
+            Ability::WallSlide => self.wall_slide,
+            Ability::WallJump => self.wall_jump,
+        }
+    }
+
+    // Returns true if the ability was newly granted
+    fn unlock(&mut self, ability: Ability) -> bool {
+        let slot = match ability {
+            Ability::Crouch => &mut self.crouch,
+            Ability::DoubleJump => &mut self.double_jump,
+            Ability::Dash => &mut self.dash,
+            Ability::WallSlide => &mut self.wall_slide,
+            Ability::WallJump => &mut self.wall_jump,
+        };
+        let newly = !*slot;
+        *slot = true;
+        newly
+    }
+}
+
+// Blocking tuning (times in seconds)
+const PARRY_WINDOW: f64 = 0.15;
+const PARRY_COOLDOWN: f64 = 0.75;  // From one raise to the next that can parry
+const BLOCK_STAMINA_COST: f64 = 25.0;
+const STAMINA_REGEN: f64 = 20.0;
+const BLOCK_HALF_ANGLE: f64 = 70.0;  // Degrees either side of facing direction
+
+const HEARTS_PER_ROW: i32 = 10;
+
+// Health tuning, in quarter hearts and seconds
+const MAX_HEALTH_CAP: f64 = 80.0;  // 20 hearts
+const POISON_INTERVAL: f64 = 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HitOutcome {
+    Ignored,
+    Parried,
+    Blocked,
+    Damaged,
 }
 
 impl Player {
@@ -228,7 +574,7 @@ impl Player {
             x: 100.0,
             y: 300.0,
             width: 24.0,
-            height: 32.0,
+            height: STAND_HEIGHT,
             vel_x: 0.0,
             vel_y: 0.0,
             speed: 5.0,
@@ -237,34 +583,133 @@ impl Player {
             facing_right: true,
             is_attacking: false,
             attack_cooldown: 0.0,
+            attack_duration: 0.4,
+            attack_bonus: 0,
             health: 28.0,  // 7 hearts * 4 quarters = 28 quarter hearts
             max_health: 28.0,
             animation_frame: 0.0,
-            sword_angle: 90.0,  // Start vertical
             damage_cooldown: 0.0,
+            iframe_duration: 1.0,
+            hit_damage: 1.0,
+            poison_tick: 0.0,
             invincible: false,
             is_dead: false,
+            is_blocking: false,
+            parry_timer: 0.0,
+            parry_cooldown: 0.0,
+            stamina: 100.0,
+            max_stamina: 100.0,
+            effects: ActiveEffects::default(),
+            inventory: Inventory::default(),
+            experience: Experience::default(),
+            abilities: Abilities::default(),
+            movement: MovementConfig::default(),
+            jump_held: false,
+            jump_buffer: 0,
+            air_frames: 0,
+            has_jumped: false,
+            can_cut_jump: false,
+            air_jumps_left: 0,
+            jumped: false,
+            air_dash_available: true,
+            input_dir: 0.0,
+            wall_contact: None,
+            is_wall_sliding: false,
+            wall_jump_lock: 0.0,
+            dash_timer: 0.0,
+            dash_cooldown: 0.0,
+            is_crouching: false,
+            on_one_way: false,
+            drop_through_timer: 0.0,
         }
     }
 
+    // Adds capacity and fills the new space, up to MAX_HEALTH_CAP
+    fn raise_max_health(&mut self, quarters: f64) {
+        let raised = (self.max_health + quarters).min(MAX_HEALTH_CAP) - self.max_health;
+        self.max_health += raised;
+        self.health = (self.health + raised).min(self.max_health);
+    }
+
+    fn heal(&mut self, quarters: f64) {
+        self.health = (self.health + quarters).min(self.max_health);
+    }
+
+    // A fresh knight with the permanent shop upgrades applied
+    fn with_upgrades(upgrades: &Upgrades) -> Self {
+        let mut player = Player::new();
+        upgrades.apply(&mut player);
+        player
+    }
+
+    // 0.0 at the start of a sword swing, 1.0 at the end
+    fn swing_progress(&self) -> f64 {
+        1.0 - self.attack_cooldown / self.attack_duration
+    }
+
     fn update(&mut self, delta: f64, platforms: &[Platform]) {
-        self.vel_y += 0.5;
-        
-        if self.vel_y > 15.0 {
-            self.vel_y = 15.0;
+        let dt = delta * 0.016;
+        let prev_x = self.x;
+        let prev_bottom = self.y + self.height;
+
+        if self.dash_timer > 0.0 {
+            // Dashes travel in a straight line
+            self.dash_timer -= dt;
+            self.vel_y = 0.0;
+        } else {
+            // Hang a little longer at the top of a held jump
+            let at_apex = !self.on_ground
+                && self.jump_held
+                && self.vel_y.abs() < self.movement.apex_threshold;
+            self.vel_y += if at_apex {
+                self.movement.gravity * self.movement.apex_gravity_multiplier
+            } else {
+                self.movement.gravity
+            };
+
+            let max_fall = if self.is_wall_sliding {
+                self.movement.wall_slide_speed
+            } else {
+                self.movement.max_fall_speed
+            };
+            if self.vel_y > max_fall {
+                self.vel_y = max_fall;
+            }
         }
 
         self.x += self.vel_x;
         self.y += self.vel_y;
 
+        if self.drop_through_timer > 0.0 {
+            self.drop_through_timer -= dt;
+        }
+
         self.on_ground = false;
+        self.on_one_way = false;
         for platform in platforms {
-            if self.check_collision(platform) {
-                if self.vel_y > 0.0 && self.y < platform.y {
-                    self.y = platform.y - self.height;
-                    self.vel_y = 0.0;
-                    self.on_ground = true;
-                }
+            if !self.check_collision(platform) {
+                continue;
+            }
+
+            let came_from_left = prev_x + self.width <= platform.x;
+            let came_from_right = prev_x >= platform.x + platform.width;
+            let one_way = platform.height <= ONE_WAY_MAX_HEIGHT;
+
+            if prev_bottom > platform.y + 1.0 && (came_from_left || came_from_right) {
+                // Ran into the side of the platform - treat it as a wall
+                self.x = if came_from_left {
+                    platform.x - self.width
+                } else {
+                    platform.x + platform.width
+                };
+                self.vel_x = 0.0;
+            } else if self.vel_y > 0.0 && self.y < platform.y
+                && !(one_way && self.drop_through_timer > 0.0)
+            {
+                self.y = platform.y - self.height;
+                self.vel_y = 0.0;
+                self.on_ground = true;
+                self.on_one_way = one_way;
             }
         }
 
@@ -274,6 +719,42 @@ impl Player {
             self.on_ground = true;
         }
 
+        if self.on_ground {
+            self.air_jumps_left = if self.abilities.has(Ability::DoubleJump) { 1 } else { 0 };
+            self.air_dash_available = true;
+            self.air_frames = 0;
+            self.has_jumped = false;
+            self.can_cut_jump = false;
+
+            // A jump pressed just before landing fires now
+            if self.jump_buffer > 0 {
+                self.jump();
+            }
+        } else {
+            self.air_frames = self.air_frames.saturating_add(1);
+        }
+        self.jump_buffer = self.jump_buffer.saturating_sub(1);
+
+        self.wall_contact = if self.on_ground { None } else { self.probe_wall(platforms) };
+        self.is_wall_sliding = self.abilities.has(Ability::WallSlide)
+            && self.vel_y > 0.0
+            && match self.wall_contact {
+                Some(WallSide::Left) => self.input_dir < 0.0,
+                Some(WallSide::Right) => self.input_dir > 0.0,
+                None => false,
+            };
+        if self.is_wall_sliding && self.vel_y > self.movement.wall_slide_speed {
+            self.vel_y = self.movement.wall_slide_speed;
+        }
+        self.input_dir = 0.0;
+
+        if self.wall_jump_lock > 0.0 {
+            self.wall_jump_lock -= dt;
+        }
+        if self.dash_cooldown > 0.0 {
+            self.dash_cooldown -= dt;
+        }
+
         if self.attack_cooldown > 0.0 {
             self.attack_cooldown -= delta * 0.016;  // Normalize delta
             
@@ -291,7 +772,33 @@ impl Player {
             }
         }
 
-        self.vel_x *= 0.85;
+        if self.parry_timer > 0.0 {
+            self.parry_timer = (self.parry_timer - delta * 0.016).max(0.0);
+        }
+        if self.parry_cooldown > 0.0 {
+            self.parry_cooldown = (self.parry_cooldown - delta * 0.016).max(0.0);
+        }
+        self.effects.tick(delta * 0.016);
+
+        // Poison wears the knight down but never finishes them off
+        if self.effects.has(EffectKind::Poison) {
+            self.poison_tick += delta * 0.016;
+            if self.poison_tick >= POISON_INTERVAL {
+                self.poison_tick -= POISON_INTERVAL;
+                self.health = (self.health - 1.0).max(self.health.min(1.0));
+            }
+        } else {
+            self.poison_tick = 0.0;
+        }
+
+        // Stamina only recovers while the shield is lowered
+        if !self.is_blocking {
+            self.stamina = (self.stamina + STAMINA_REGEN * delta * 0.016).min(self.max_stamina);
+        }
+
+        if self.dash_timer <= 0.0 {
+            self.vel_x *= 0.85;
+        }
         
         if self.vel_x.abs() > 0.1 {
             self.animation_frame += 0.2;
@@ -310,42 +817,241 @@ impl Player {
         self.y + self.height > platform.y
     }
 
+    // Which side, if any, has a wall directly against the knight's body
+    fn probe_wall(&self, platforms: &[Platform]) -> Option<WallSide> {
+        for platform in platforms {
+            let overlaps_vertically = self.y < platform.y + platform.height
+                && self.y + self.height > platform.y + 1.0;
+            if !overlaps_vertically {
+                continue;
+            }
+            if (self.x - (platform.x + platform.width)).abs() <= 1.0 {
+                return Some(WallSide::Left);
+            }
+            if (self.x + self.width - platform.x).abs() <= 1.0 {
+                return Some(WallSide::Right);
+            }
+        }
+        None
+    }
+
+    fn jump_input(&mut self, pressed: bool, released: bool, down: bool) {
+        if pressed {
+            self.jump_held = true;
+            self.try_jump(down);
+        }
+
+        // Letting go early cuts the jump short
+        if released {
+            self.jump_held = false;
+            if self.can_cut_jump && self.vel_y < 0.0 {
+                self.vel_y *= self.movement.jump_cut_multiplier;
+                self.can_cut_jump = false;
+            }
+        }
+    }
+
+    fn try_jump(&mut self, down: bool) {
+        if self.on_ground && down && self.on_one_way && self.abilities.has(Ability::Crouch) {
+            self.drop_through();
+        } else if self.on_ground || self.in_coyote_time() {
+            self.jump();
+        } else if self.abilities.has(Ability::WallJump) && self.wall_contact.is_some() {
+            self.wall_jump();
+        } else if self.air_jumps_left > 0 {
+            self.air_jumps_left -= 1;
+            self.vel_y = -self.jump_power * 0.85;
+            self.can_cut_jump = true;
+            self.jumped = true;
+        } else {
+            // Nothing to jump off yet - remember the press until landing
+            self.jump_buffer = self.movement.jump_buffer_frames;
+        }
+    }
+
+    fn in_coyote_time(&self) -> bool {
+        !self.has_jumped && self.air_frames <= self.movement.coyote_frames
+    }
+
     fn jump(&mut self) {
-        if self.on_ground {
-            self.vel_y = -self.jump_power;
+        self.set_crouching(false);
+        self.vel_y = -self.jump_power;
+        self.on_ground = false;
+        self.has_jumped = true;
+        self.can_cut_jump = true;
+        self.jump_buffer = 0;
+        self.jumped = true;
+    }
+
+    fn wall_jump(&mut self) {
+        // Kick away from the wall and briefly ignore steering back into it
+        let away = match self.wall_contact {
+            Some(WallSide::Left) => 1.0,
+            Some(WallSide::Right) => -1.0,
+            None => return,
+        };
+        self.vel_x = away * self.speed * 1.4;
+        self.vel_y = -self.jump_power * 0.9;
+        self.facing_right = away > 0.0;
+        self.wall_jump_lock = 0.15;
+        self.is_wall_sliding = false;
+        self.has_jumped = true;
+        self.can_cut_jump = true;
+        self.jumped = true;
+    }
+
+    fn drop_through(&mut self) {
+        self.set_crouching(false);
+        self.drop_through_timer = 0.25;
+        self.on_ground = false;
+        self.y += 1.0;
+    }
+
+    fn dash(&mut self) {
+        if !self.abilities.has(Ability::Dash) || self.dash_cooldown > 0.0 || self.is_crouching {
+            return;
+        }
+        if !self.on_ground {
+            if !self.air_dash_available {
+                return;
+            }
+            self.air_dash_available = false;
+        }
+        let speed = self.movement.dash_speed;
+        self.vel_x = if self.facing_right { speed } else { -speed };
+        self.vel_y = 0.0;
+        self.dash_timer = self.movement.dash_duration;
+        self.dash_cooldown = self.movement.dash_cooldown;
+    }
+
+    fn set_crouching(&mut self, held: bool) {
+        let want = held && self.on_ground && self.abilities.has(Ability::Crouch) && self.dash_timer <= 0.0;
+        if want && !self.is_crouching {
+            // Keep the feet planted while the hitbox shrinks
+            self.height = CROUCH_HEIGHT;
+            self.y += STAND_HEIGHT - CROUCH_HEIGHT;
+            self.is_crouching = true;
+        } else if !want && self.is_crouching {
+            self.height = STAND_HEIGHT;
+            self.y -= STAND_HEIGHT - CROUCH_HEIGHT;
+            self.is_crouching = false;
+        }
+    }
+
+    fn can_steer(&self) -> bool {
+        self.wall_jump_lock <= 0.0 && self.dash_timer <= 0.0
+    }
+
+    // `amount` scales run speed for analog sticks; keyboard input passes 1.0
+    fn move_left(&mut self, amount: f64) {
+        self.input_dir = -1.0;
+        if self.can_steer() {
+            self.vel_x = -self.move_speed() * amount;
+            self.facing_right = false;
+        }
+    }
+
+    fn move_right(&mut self, amount: f64) {
+        self.input_dir = 1.0;
+        if self.can_steer() {
+            self.vel_x = self.move_speed() * amount;
+            self.facing_right = true;
+        }
+    }
+
+    fn move_speed(&self) -> f64 {
+        // Shuffle forward behind the shield or while crawling
+        let speed = if self.effects.has(EffectKind::SpeedBoots) {
+            self.speed * 1.5
+        } else {
+            self.speed
+        };
+        if self.is_blocking {
+            speed * 0.5
+        } else if self.is_crouching {
+            speed * 0.4
+        } else {
+            speed
+        }
+    }
+
+    // Hit points a sword strike takes off an enemy
+    fn sword_damage(&self) -> i32 {
+        let flame = if self.effects.has(EffectKind::FlamingSword) { 1 } else { 0 };
+        1 + self.attack_bonus + flame
+    }
+
+    fn set_blocking(&mut self, held: bool) {
+        if held && !self.is_blocking {
+            // Can't raise the shield mid-swing or with no stamina left
+            if self.is_attacking || self.stamina < BLOCK_STAMINA_COST {
+                return;
+            }
+            self.is_blocking = true;
+            // Tapping block can't chain parries; a raise inside the
+            // cooldown is a plain block
+            if self.parry_cooldown <= 0.0 {
+                self.parry_timer = PARRY_WINDOW;
+                self.parry_cooldown = PARRY_COOLDOWN;
+            }
+        } else if !held {
+            self.is_blocking = false;
+            self.parry_timer = 0.0;
         }
     }
 
-    fn move_left(&mut self) {
-        self.vel_x = -self.speed;
-        self.facing_right = false;
+    // True if a point lies within the shield's arc in front of the knight
+    fn is_frontal(&self, source_x: f64, source_y: f64) -> bool {
+        let mut dx = source_x - (self.x + self.width / 2.0);
+        let dy = source_y - (self.y + self.height / 2.0);
+        if !self.facing_right {
+            dx = -dx;
+        }
+        dy.atan2(dx).abs() <= BLOCK_HALF_ANGLE.to_radians()
     }
 
-    fn move_right(&mut self) {
-        self.vel_x = self.speed;
-        self.facing_right = true;
+    // Resolve an incoming hit from a source at (source_x, source_y)
+    fn receive_hit(&mut self, source_x: f64, source_y: f64) -> HitOutcome {
+        if self.invincible || self.is_dead || self.effects.has(EffectKind::Invincibility) {
+            return HitOutcome::Ignored;
+        }
+
+        if self.is_blocking && self.is_frontal(source_x, source_y) {
+            if self.parry_timer > 0.0 {
+                return HitOutcome::Parried;
+            }
+            if self.stamina >= BLOCK_STAMINA_COST {
+                self.stamina -= BLOCK_STAMINA_COST;
+                return HitOutcome::Blocked;
+            }
+            // Guard broken - shield drops and the hit lands
+            self.is_blocking = false;
+        }
+
+        // A magic shield soaks up one hit from any side
+        if self.effects.remove(EffectKind::Shield) {
+            return HitOutcome::Blocked;
+        }
+
+        self.take_damage();
+        HitOutcome::Damaged
     }
 
     fn attack(&mut self) {
-        if self.attack_cooldown <= 0.0 {
-            console_log!("SWORD SWING!");
+        if self.attack_cooldown <= 0.0 && !self.is_blocking {
             self.is_attacking = true;
-            self.attack_cooldown = 0.4;
+            self.attack_cooldown = self.attack_duration;
             // Sword starts vertical and will swing
         }
     }
     
     fn take_damage(&mut self) {
         if !self.invincible && self.damage_cooldown <= 0.0 && !self.is_dead {
-            self.health -= 1.0;  // 1 quarter heart damage
+            self.health -= self.hit_damage;
             self.invincible = true;
-            self.damage_cooldown = 1.0;  // 1 second of invincibility
-            
-            let hearts_remaining = self.health / 4.0;
-            console_log!("Player took damage! Hearts: {:.2}", hearts_remaining);
+            self.damage_cooldown = self.iframe_duration;
             
             if self.health <= 0.0 {
-                console_log!("GAME OVER!");
                 self.health = 0.0;
                 self.is_dead = true;
             }
@@ -354,7 +1060,7 @@ impl Player {
 
     fn get_sword_hitbox(&self) -> (f64, f64, f64, f64) {
         if self.is_attacking {
-            let swing_progress = 1.0 - (self.attack_cooldown / 0.4);
+            let swing_progress = self.swing_progress();
             
             // Calculate sword position based on rotation
             let start_angle = 90.0_f64.to_radians();
@@ -388,8 +1094,27 @@ impl Player {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum EnemyKind {
+    Goblin,
+    Brute,  // Slow and tough, found further out
+}
+
+// Distance in meters before brutes can spawn
+const BRUTE_MIN_METERS: f64 = 150.0;
+
+impl EnemyKind {
+    fn xp(self) -> u32 {
+        match self {
+            EnemyKind::Goblin => 10,
+            EnemyKind::Brute => 30,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Enemy {
+    kind: EnemyKind,
     x: f64,
     y: f64,
     width: f64,
@@ -402,23 +1127,34 @@ struct Enemy {
     speed: f64,
     is_alive: bool,
     hit_flash: f64,
+    stagger: f64,  // Seconds left stunned after being blocked or parried
 }
 
 impl Enemy {
     fn new(x: f64, y: f64, patrol_range: f64) -> Self {
+        Enemy::spawn(EnemyKind::Goblin, x, y, patrol_range)
+    }
+
+    fn spawn(kind: EnemyKind, x: f64, y: f64, patrol_range: f64) -> Self {
+        let (width, height, health, speed) = match kind {
+            EnemyKind::Goblin => (21.0, 27.0, 1, 1.5),
+            EnemyKind::Brute => (28.0, 36.0, 3, 0.9),
+        };
         Enemy {
+            kind,
             x,
             y,
-            width: 21.0,
-            height: 27.0,
-            vel_x: 1.5,
+            width,
+            height,
+            vel_x: speed,
             vel_y: 0.0,
-            health: 1,
+            health,
             patrol_start: x - patrol_range / 2.0,
             patrol_end: x + patrol_range / 2.0,
-            speed: 1.5,
+            speed,
             is_alive: true,
             hit_flash: 0.0,
+            stagger: 0.0,
         }
     }
 
@@ -440,16 +1176,26 @@ impl Enemy {
         self.x += self.vel_x;
         self.y += self.vel_y;
 
-        if self.x <= self.patrol_start || self.x >= self.patrol_end {
+        if self.stagger > 0.0 {
+            // Slide to a halt, then resume patrolling in the direction of travel
+            self.vel_x *= 0.85;
+            self.stagger -= delta * 0.016;
+            if self.stagger <= 0.0 {
+                self.stagger = 0.0;
+                self.vel_x = if self.x < (self.patrol_start + self.patrol_end) / 2.0 {
+                    self.speed
+                } else {
+                    -self.speed
+                };
+            }
+        } else if self.x <= self.patrol_start || self.x >= self.patrol_end {
             self.vel_x = -self.vel_x;
         }
 
         for platform in platforms {
-            if self.check_collision(platform) {
-                if self.vel_y > 0.0 && self.y < platform.y {
-                    self.y = platform.y - self.height;
-                    self.vel_y = 0.0;
-                }
+            if self.check_collision(platform) && self.vel_y > 0.0 && self.y < platform.y {
+                self.y = platform.y - self.height;
+                self.vel_y = 0.0;
             }
         }
 
@@ -466,8 +1212,8 @@ impl Enemy {
         self.y + self.height > platform.y
     }
 
-    fn take_damage(&mut self, from_right: bool) {
-        self.health -= 1;
+    fn take_damage(&mut self, from_right: bool, damage: i32) {
+        self.health -= damage;
         self.hit_flash = 1.0;
         
         // Add knockback
@@ -478,9 +1224,14 @@ impl Enemy {
             self.is_alive = false;
         }
     }
+
+    fn stagger_for(&mut self, seconds: f64, from_right: bool, knockback: f64) {
+        self.stagger = seconds;
+        self.vel_x = if from_right { -knockback } else { knockback };
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Platform {
     x: f64,
     y: f64,
@@ -488,11 +1239,12 @@ struct Platform {
     height: f64,
 }
 
-#[derive(Clone)]
-struct HeartPickup {
+#[derive(Clone, Serialize, Deserialize)]
+struct Pickup {
     x: f64,
     y: f64,
     size: f64,
+    kind: PickupKind,
     collected: bool,
     float_offset: f64,
 }
@@ -503,12 +1255,13 @@ impl Platform {
     }
 }
 
-impl HeartPickup {
-    fn new(x: f64, y: f64) -> Self {
-        HeartPickup {
+impl Pickup {
+    fn new(x: f64, y: f64, kind: PickupKind) -> Self {
+        Pickup {
             x,
             y,
             size: 20.0,
+            kind,
             collected: false,
             float_offset: 0.0,
         }
@@ -528,65 +1281,316 @@ impl HeartPickup {
     }
 }
 
-struct Game {
-    player: Player,
-    enemies: Vec<Enemy>,
-    platforms: Vec<Platform>,
-    hearts: Vec<HeartPickup>,
-    camera_x: f64,
-    keys: KeyState,
-    distance_traveled: f64,
-    last_platform_x: f64,
-    next_platform_seed: u32,
-    game_time: f64,
+#[derive(Clone, Serialize, Deserialize)]
+struct Coin {
+    x: f64,
+    y: f64,
+    collected: bool,
 }
 
-#[derive(Default)]
-struct KeyState {
-    left: bool,
-    right: bool,
-    up: bool,
-    space: bool,
+impl Coin {
+    const SIZE: f64 = 12.0;
+
+    fn new(x: f64, y: f64) -> Self {
+        Coin { x, y, collected: false }
+    }
+
+    fn check_collision(&self, player: &Player) -> bool {
+        !self.collected &&
+        player.x < self.x + Coin::SIZE &&
+        player.x + player.width > self.x &&
+        player.y < self.y + Coin::SIZE &&
+        player.y + player.height > self.y
+    }
 }
 
-impl Game {
-    fn new() -> Self {
-        // Start with a few initial platforms
-        let mut platforms = vec![
-            Platform::new(0.0, 450.0, 200.0, 50.0),  // Starting ground
-            Platform::new(200.0, 400.0, 100.0, 20.0),
-            Platform::new(350.0, 350.0, 80.0, 20.0),
-        ];
-        
-        let mut enemies = vec![
-            Enemy::new(250.0, 350.0, 80.0),
-        ];
-        
-        let mut game = Game {
-            player: Player::new(),
-            enemies,
-            platforms,
-            hearts: Vec::new(),
-            camera_x: 0.0,
-            keys: KeyState::default(),
-            distance_traveled: 0.0,
-            last_platform_x: 350.0,
-            next_platform_seed: 1,
-            game_time: 0.0,
+// Thrown item tuning (times in seconds)
+const POTION_HEAL: f64 = 8.0;  // Quarter hearts
+const DAGGER_SPEED: f64 = 10.0;
+const DAGGER_RANGE: f64 = 0.8;
+const BOMB_FUSE: f64 = 1.5;
+const BOMB_RADIUS: f64 = 70.0;
+const BOMB_DAMAGE: i32 = 3;
+const EXPLOSION_TIME: f64 = 0.3;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Projectile {
+    kind: ItemKind,  // Dagger or Bomb
+    x: f64,
+    y: f64,
+    vel_x: f64,
+    vel_y: f64,
+    timer: f64,  // Dagger flight time or bomb fuse left
+    explosion: Option<f64>,  // Seconds since a bomb went off
+    done: bool,
+}
+
+impl Projectile {
+    fn thrown(kind: ItemKind, player: &Player) -> Self {
+        let dir = if player.facing_right { 1.0 } else { -1.0 };
+        let (vel_x, vel_y, timer) = match kind {
+            ItemKind::Bomb => (6.0 * dir, -7.0, BOMB_FUSE),
+            _ => (DAGGER_SPEED * dir, 0.0, DAGGER_RANGE),
         };
-        
-        // Generate initial platforms
-        game.generate_platforms_ahead();
-        
-        game
+        Projectile {
+            kind,
+            x: player.x + player.width / 2.0,
+            y: player.y + 12.0,
+            vel_x,
+            vel_y,
+            timer,
+            explosion: None,
+            done: false,
+        }
     }
-    
-    fn generate_platforms_ahead(&mut self) {
-        // Generate platforms up to 1000 pixels ahead of the last platform
-        while self.last_platform_x < self.camera_x + 1200.0 {
-            // Use simple pseudo-random for platform generation
-            self.next_platform_seed = (self.next_platform_seed * 1103515245 + 12345) & 0x7fffffff;
-            
+
+    // Returns true on the frame a bomb detonates
+    fn update(&mut self, delta: f64, platforms: &[Platform]) -> bool {
+        let dt = delta * 0.016;
+        if let Some(elapsed) = &mut self.explosion {
+            *elapsed += dt;
+            self.done = *elapsed >= EXPLOSION_TIME;
+            return false;
+        }
+
+        self.x += self.vel_x * delta;
+        self.y += self.vel_y * delta;
+        self.timer -= dt;
+
+        match self.kind {
+            ItemKind::Bomb => {
+                self.vel_y = (self.vel_y + 0.5 * delta).min(15.0);
+                let landed = self.y >= 450.0 || platforms.iter().any(|platform| {
+                    self.x > platform.x && self.x < platform.x + platform.width &&
+                    self.y >= platform.y && self.y <= platform.y + platform.height
+                });
+                if landed || self.timer <= 0.0 {
+                    self.explosion = Some(0.0);
+                    return true;
+                }
+            }
+            _ => self.done = self.timer <= 0.0,
+        }
+        false
+    }
+
+    fn hits(&self, enemy: &Enemy) -> bool {
+        self.x > enemy.x && self.x < enemy.x + enemy.width &&
+        self.y > enemy.y && self.y < enemy.y + enemy.height
+    }
+}
+
+const ENEMY_COIN_DROP: u32 = 3;
+const MAGNET_RADIUS: f64 = 160.0;
+const MAGNET_PULL: f64 = 7.0;  // Pixels per frame
+
+// Distance between checkpoint banners (10 pixels per meter)
+const CHECKPOINT_INTERVAL: f64 = 1500.0;
+
+// Banner planted on a platform; x is the pole, y the platform surface
+#[derive(Clone, Serialize, Deserialize)]
+struct Checkpoint {
+    x: f64,
+    y: f64,
+    reached: bool,
+}
+
+impl Checkpoint {
+    fn new(x: f64, y: f64) -> Self {
+        Checkpoint { x, y, reached: false }
+    }
+
+    fn check_collision(&self, player: &Player) -> bool {
+        !self.reached &&
+        player.x < self.x + 16.0 &&
+        player.x + player.width > self.x &&
+        player.y < self.y &&
+        player.y + player.height > self.y - 48.0
+    }
+}
+
+// Where the knight comes back after losing a life
+#[derive(Clone, Serialize, Deserialize)]
+struct RespawnPoint {
+    x: f64,
+    y: f64,  // Surface the knight stands on
+    health: f64,  // Health when the checkpoint was touched
+}
+
+const LIVES_MODE_LIVES: u32 = 3;
+const RESPAWN_HEALTH_FRACTION: f64 = 0.5;  // Minimum share of max health on respawn
+const RESPAWN_START: (f64, f64) = (100.0, 450.0);  // On the starting ground
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+enum RunMode {
+    Standard,  // One life; dying ends the run
+    Lives,     // Extra lives respawn the knight at the last checkpoint
+    Daily,     // One life on the day's seed, with its modifiers
+}
+
+impl RunMode {
+    // Also the high score board the run is recorded on
+    fn name(self) -> &'static str {
+        match self {
+            RunMode::Standard => STANDARD_MODE,
+            RunMode::Lives => LIVES_MODE,
+            RunMode::Daily => DAILY_MODE,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            RunMode::Standard => "Standard",
+            RunMode::Lives => "Lives",
+            RunMode::Daily => "Daily",
+        }
+    }
+
+    fn toggled(self) -> RunMode {
+        match self {
+            RunMode::Standard => RunMode::Lives,
+            RunMode::Lives => RunMode::Daily,
+            RunMode::Daily => RunMode::Standard,
+        }
+    }
+
+    fn starting_lives(self) -> u32 {
+        match self {
+            RunMode::Standard | RunMode::Daily => 0,
+            RunMode::Lives => LIVES_MODE_LIVES,
+        }
+    }
+}
+
+// How far in front of the knight the auto-attack assist swings at enemies
+const AUTO_ATTACK_REACH: f64 = 40.0;
+
+// Screen shake when the knight is hurt or a bomb goes off
+const SHAKE_TIME: f64 = 0.25;  // Seconds
+const SHAKE_STRENGTH: f64 = 4.0;  // Pixels at the start of the shake
+
+#[derive(Serialize, Deserialize)]
+struct Game {
+    player: Player,
+    enemies: Vec<Enemy>,
+    platforms: Vec<Platform>,
+    pickups: Vec<Pickup>,
+    coins: Vec<Coin>,
+    projectiles: Vec<Projectile>,
+    camera_x: f64,
+    distance_traveled: f64,
+    last_platform_x: f64,
+    seed: u32,  // Seed the run started from, for retries
+    next_platform_seed: u32,
+    game_time: f64,
+    toast: Option<(String, f64)>,  // Message and seconds left on screen
+    score: ScoreTracker,
+    mode: RunMode,
+    lives: u32,  // Spare lives left in lives mode
+    checkpoints: Vec<Checkpoint>,
+    next_checkpoint_x: f64,
+    checkpoints_reached: u32,
+    respawn: Option<RespawnPoint>,  // Last checkpoint touched
+    upgrades: Upgrades,
+    coins_collected: u32,
+    difficulty: Difficulty,
+    modifiers: Modifiers,
+    assists: Assists,
+    assists_used: Assists,  // Every assist switched on at some point in the run
+    shake: f64,  // Seconds of screen shake left
+    #[serde(skip)]
+    events: Vec<GameEvent>,  // This tick's events, drained by the engine
+    #[serde(skip)]
+    particles: Particles,
+}
+
+// Seed for a fresh run; the level generator needs a non-zero value
+fn random_seed() -> u32 {
+    let mut bytes = [0u8; 4];
+    if getrandom::getrandom(&mut bytes).is_err() {
+        return 1;
+    }
+    (u32::from_le_bytes(bytes) & 0x7fffffff).max(1)
+}
+
+impl Game {
+    fn new(seed: u32, mode: RunMode, upgrades: &Upgrades, difficulty: Difficulty) -> Self {
+        Game::with_modifiers(seed, mode, upgrades, difficulty, Modifiers::default())
+    }
+
+    fn with_modifiers(
+        seed: u32,
+        mode: RunMode,
+        upgrades: &Upgrades,
+        difficulty: Difficulty,
+        modifiers: Modifiers,
+    ) -> Self {
+        // Start with a few initial platforms
+        let platforms = vec![
+            Platform::new(0.0, 450.0, 200.0, 50.0),  // Starting ground
+            Platform::new(200.0, 400.0, 100.0, 20.0),
+            Platform::new(350.0, 350.0, 80.0, 20.0),
+        ];
+        
+        let enemies = vec![
+            Enemy::new(250.0, 350.0, 80.0),
+        ];
+        
+        let mut player = Player::with_upgrades(upgrades);
+        difficulty.apply(&mut player);
+        modifiers.apply(&mut player);
+
+        let mut game = Game {
+            player,
+            enemies,
+            platforms,
+            pickups: Vec::new(),
+            coins: Vec::new(),
+            projectiles: Vec::new(),
+            camera_x: 0.0,
+            distance_traveled: 0.0,
+            last_platform_x: 350.0,
+            seed,
+            next_platform_seed: seed,
+            game_time: 0.0,
+            toast: None,
+            score: ScoreTracker::default(),
+            mode,
+            lives: mode.starting_lives(),
+            checkpoints: Vec::new(),
+            next_checkpoint_x: CHECKPOINT_INTERVAL,
+            checkpoints_reached: 0,
+            respawn: None,
+            upgrades: upgrades.clone(),
+            coins_collected: 0,
+            difficulty,
+            modifiers,
+            assists: Assists::default(),
+            assists_used: Assists::default(),
+            shake: 0.0,
+            events: Vec::new(),
+            particles: Particles::default(),
+        };
+        
+        // Generate initial platforms
+        game.generate_platforms_ahead();
+        
+        game
+    }
+    
+    fn next_random(&mut self) -> u32 {
+        self.next_platform_seed = self.next_platform_seed
+            .wrapping_mul(1103515245)
+            .wrapping_add(12345) & 0x7fffffff;
+        self.next_platform_seed
+    }
+
+    fn generate_platforms_ahead(&mut self) {
+        // Generate platforms up to 1000 pixels ahead of the last platform
+        while self.last_platform_x < self.camera_x + 1200.0 {
+            // Use simple pseudo-random for platform generation
+            self.next_random();
+            
             // Random gap between platforms (50-200 pixels)
             let gap = 50.0 + (self.next_platform_seed % 150) as f64;
             
@@ -600,21 +1604,50 @@ impl Game {
             // Create new platform
             let x = self.last_platform_x + gap + width / 2.0;
             self.platforms.push(Platform::new(x, y, width, 20.0));
+
+            // Plant a banner on the first platform past each interval and
+            // keep its platform clear of goblins
+            let checkpoint = x >= self.next_checkpoint_x;
+            if checkpoint {
+                self.checkpoints.push(Checkpoint::new(x + width / 2.0 - 8.0, y));
+                self.next_checkpoint_x += CHECKPOINT_INTERVAL;
+            }
             
-            // Chance to spawn enemy on platform (40% chance)
-            if self.next_platform_seed % 100 < 40 {
+            // Chance to spawn enemy on platform (40% chance on Knight)
+            let enemy_chance = 40.0 * self.difficulty.enemy_rate() * self.modifiers.enemy_rate();
+            if !checkpoint && self.next_platform_seed % 100 < enemy_chance as u32 {
                 // Increase enemy density as player progresses
                 let difficulty_multiplier = (self.distance_traveled / 1000.0).min(3.0);
-                if self.next_platform_seed % 100 < (40.0 * difficulty_multiplier) as u32 {
-                    self.enemies.push(Enemy::new(x + width / 4.0, y - 50.0, width * 0.8));
+                if self.next_platform_seed % 100 < (enemy_chance * difficulty_multiplier) as u32 {
+                    // One in four is a brute once they start appearing
+                    let kind = if self.distance_traveled / 10.0 >= BRUTE_MIN_METERS
+                        && (self.next_platform_seed / 100).is_multiple_of(4)
+                    {
+                        EnemyKind::Brute
+                    } else {
+                        EnemyKind::Goblin
+                    };
+                    self.enemies.push(Enemy::spawn(kind, x + width / 4.0, y - 50.0, width * 0.8));
                 }
             }
             
-            // Chance to spawn heart pickup (15% chance)
-            self.next_platform_seed = (self.next_platform_seed * 1103515245 + 12345) & 0x7fffffff;
-            if self.next_platform_seed % 100 < 15 {
-                // Place heart above platform
-                self.hearts.push(HeartPickup::new(x, y - 40.0));
+            // Chance of a heart or power-up hovering above the platform
+            self.next_random();
+            if self.next_platform_seed % 100 < effects::PICKUP_CHANCE {
+                let roll = self.next_random();
+                let meters = (self.distance_traveled / 10.0) as u32;
+                let heart_rate = self.difficulty.heart_rate() * self.modifiers.heart_rate();
+                let kind = effects::roll_pickup(roll, meters, heart_rate);
+                self.pickups.push(Pickup::new(x, y - 40.0, kind));
+            }
+
+            // Chance of a row of coins along the platform (25% chance)
+            self.next_random();
+            if self.next_platform_seed % 100 < 25 {
+                let count = (width / 30.0) as usize;
+                for i in 0..count {
+                    self.coins.push(Coin::new(x + 9.0 + i as f64 * 30.0, y - 24.0));
+                }
             }
             
             self.last_platform_x = x + width / 2.0;
@@ -622,60 +1655,291 @@ impl Game {
     }
     
     fn cleanup_behind(&mut self) {
-        // Remove platforms and enemies that are too far behind the camera
-        let cleanup_x = self.camera_x - 500.0;
+        // Remove platforms and enemies that are too far behind the camera,
+        // keeping the stretch from the respawn checkpoint onwards. Before
+        // the first one, a spare life respawns on the starting ground.
+        let mut cleanup_x = self.camera_x - 500.0;
+        if let Some(respawn) = &self.respawn {
+            cleanup_x = cleanup_x.min(respawn.x - 500.0);
+        } else if self.lives > 0 {
+            cleanup_x = cleanup_x.min(RESPAWN_START.0 - 500.0);
+        }
         
         self.platforms.retain(|platform| platform.x + platform.width > cleanup_x);
-        self.enemies.retain(|enemy| enemy.x > cleanup_x || !enemy.is_alive);
-        self.hearts.retain(|heart| heart.x > cleanup_x && !heart.collected);
+        self.enemies.retain(|enemy| enemy.x > cleanup_x);
+        self.pickups.retain(|pickup| pickup.x > cleanup_x && !pickup.collected);
+        self.coins.retain(|coin| coin.x > cleanup_x && !coin.collected);
+        self.checkpoints.retain(|checkpoint| checkpoint.x > cleanup_x);
+    }
+
+    fn use_item(&mut self) {
+        let Some(kind) = self.player.inventory.selected_item() else {
+            return;
+        };
+        // Potions are kept for when they are needed
+        if kind == ItemKind::Potion
+            && self.player.health >= self.player.max_health
+            && !self.player.effects.has(EffectKind::Poison)
+        {
+            return;
+        }
+        self.player.inventory.take_selected();
+
+        match kind {
+            ItemKind::Potion => {
+                self.player.heal(POTION_HEAL);
+                self.player.effects.remove(EffectKind::Poison);
+            }
+            ItemKind::Dagger | ItemKind::Bomb => {
+                self.projectiles.push(Projectile::thrown(kind, &self.player));
+            }
+        }
+        self.events.push(GameEvent::ItemUsed { item: kind });
+    }
+
+    // Score, XP and coin drop for a goblin that just fell
+    fn enemy_defeated(&mut self, x: f64, y: f64, kind: EnemyKind) {
+        self.events.push(GameEvent::EnemyKilled { kind, x, y, airborne: !self.player.on_ground });
+        if self.player.experience.gain(kind.xp()) > 0 {
+            self.events.push(GameEvent::LevelUp { level: self.player.experience.level });
+        }
+        // Spill coins around where the goblin fell
+        for i in 0..ENEMY_COIN_DROP {
+            let offset = (i as f64 - 1.0) * 14.0;
+            self.coins.push(Coin::new(x + 4.0 + offset, y + 8.0));
+        }
+    }
+
+    // Spend a life and bring the knight back at the last checkpoint (or the
+    // start) with at least part of their hearts
+    fn respawn(&mut self) {
+        self.lives -= 1;
+        let (x, y, health) = match &self.respawn {
+            Some(point) => (point.x, point.y, point.health),
+            None => (RESPAWN_START.0, RESPAWN_START.1, 0.0),
+        };
+
+        let mut player = Player::with_upgrades(&self.upgrades);
+        self.difficulty.apply(&mut player);
+        // Progress made during the run is kept, timed effects are not
+        player.abilities = self.player.abilities.clone();
+        player.movement = self.player.movement.clone();
+        player.max_health = self.player.max_health;
+        player.speed = self.player.speed;
+        player.jump_power = self.player.jump_power;
+        player.attack_bonus = self.player.attack_bonus;
+        player.experience = self.player.experience.clone();
+        player.inventory = self.player.inventory.clone();
+        player.x = x;
+        player.y = y - player.height;
+        // Rounded up to whole hearts
+        let minimum = (player.max_health * RESPAWN_HEALTH_FRACTION / 4.0).ceil() * 4.0;
+        player.health = health.max(minimum).min(player.max_health);
+        player.invincible = true;
+        player.damage_cooldown = 2.0;
+        self.player = player;
+
+        self.events.push(GameEvent::PlayerRespawned { lives: self.lives });
+    }
+
+    fn set_assists(&mut self, assists: Assists) {
+        if self.mode == RunMode::Daily {
+            return;
+        }
+        self.assists = assists;
+        self.assists_used = self.assists_used.union(assists);
+    }
+
+    // Music for where the run has got to, sped up while a brute is on screen
+    fn tune(&self) -> Tune {
+        let boss = self.enemies.iter().any(|enemy| {
+            enemy.is_alive
+                && enemy.kind == EnemyKind::Brute
+                && enemy.x + enemy.width > self.camera_x
+                && enemy.x < self.camera_x + ui::SCREEN.w
+        });
+        (Track::for_distance(self.distance_traveled / 10.0), boss)
+    }
+
+    // A live enemy close enough in front for a swing to land
+    fn enemy_in_reach(&self) -> bool {
+        let player = &self.player;
+        let facing = if player.facing_right { 1.0 } else { -1.0 };
+        self.enemies.iter().filter(|enemy| enemy.is_alive).any(|enemy| {
+            let ahead = (enemy.x + enemy.width / 2.0 - (player.x + player.width / 2.0)) * facing;
+            ahead >= 0.0
+                && ahead <= (player.width + enemy.width) / 2.0 + AUTO_ATTACK_REACH
+                && enemy.y < player.y + player.height
+                && enemy.y + enemy.height > player.y
+        })
     }
 
-    fn update(&mut self, delta: f64) {
+    fn update(&mut self, delta: f64, keys: &KeyState) {
         // Don't update if player is dead
         if self.player.is_dead {
             return;
         }
+        let delta = if self.assists.slow_motion { delta * SLOW_MOTION_SPEED } else { delta };
+        // Topped up before anything can land, so a hit is still felt but never fatal
+        if self.assists.infinite_health {
+            self.player.health = self.player.max_health;
+        }
         
         // Update game time
         self.game_time += delta;
         
-        if self.keys.left {
-            self.player.move_left();
+        let axis = keys.move_axis();
+        if axis < 0.0 {
+            self.player.move_left(-axis);
+        } else if axis > 0.0 {
+            self.player.move_right(axis);
         }
-        if self.keys.right {
-            self.player.move_right();
+        self.player.set_crouching(keys.held(Action::Down));
+        self.player.jump_input(
+            keys.pressed(Action::Jump),
+            keys.released(Action::Jump),
+            keys.held(Action::Down),
+        );
+        let was_attacking = self.player.is_attacking;
+        if keys.pressed(Action::Attack) || (self.assists.auto_attack && self.enemy_in_reach()) {
+            self.player.attack();
         }
-        if self.keys.up {
-            self.player.jump();
+        if self.player.is_attacking && !was_attacking {
+            self.events.push(GameEvent::SwordSwung);
         }
-        if self.keys.space {
-            self.player.attack();
+        if keys.pressed(Action::Dash) {
+            self.player.dash();
+        }
+        self.player.set_blocking(keys.held(Action::Block));
+        if keys.pressed(Action::CycleItem) {
+            self.player.inventory.cycle();
+        }
+        if keys.pressed(Action::UseItem) {
+            self.use_item();
         }
 
         self.player.update(delta, &self.platforms);
+        // Set by the jump press above or a buffered jump firing on landing
+        if std::mem::take(&mut self.player.jumped) {
+            self.events.push(GameEvent::PlayerJumped);
+        }
         
         for enemy in &mut self.enemies {
             enemy.update(delta, &self.platforms);
         }
         
-        // Update heart pickups
-        for heart in &mut self.hearts {
-            heart.update(self.game_time);
-            
-            // Check if player collects heart
-            if heart.check_collision(&self.player) {
-                // Heal player (1 full heart = 4 quarter hearts)
-                if self.player.health < 28.0 {
-                    self.player.health = (self.player.health + 4.0).min(28.0);
-                    heart.collected = true;
-                    console_log!("Heart collected! Health: {}", self.player.health / 4.0);
+        for pickup in &mut self.pickups {
+            pickup.update(self.game_time);
+            if !pickup.check_collision(&self.player) {
+                continue;
+            }
+
+            let at_full_health = self.player.health >= self.player.max_health;
+            match pickup.kind {
+                PickupKind::Heart => {
+                    // Heal player (1 full heart = 4 quarter hearts); at full
+                    // health the heart is still worth its points
+                    self.player.heal(4.0);
+                    pickup.collected = true;
+                }
+                PickupKind::HeartContainer => {
+                    self.player.raise_max_health(4.0);
+                    pickup.collected = true;
+                    self.toast = Some(("HEART CONTAINER!".to_string(), 2.0));
+                }
+                PickupKind::PowerUp(effect) => {
+                    self.player.effects.grant(effect);
+                    pickup.collected = true;
+                    self.toast = Some((format!("{}!", effect.name()), 2.0));
+                }
+                PickupKind::Item(item) => {
+                    // Left in the world when every slot is full
+                    if self.player.inventory.add(item) {
+                        pickup.collected = true;
+                        self.toast = Some((format!("{}!", item.name()), 2.0));
+                    }
+                }
+            }
+            if pickup.collected {
+                self.events.push(GameEvent::PickupCollected {
+                    kind: pickup.kind,
+                    x: pickup.x + 10.0,
+                    y: pickup.y + pickup.float_offset + 10.0,
+                    at_full_health,
+                });
+            }
+        }
+
+        let magnet = self.player.effects.has(EffectKind::Magnet);
+        let (center_x, center_y) = (
+            self.player.x + self.player.width / 2.0,
+            self.player.y + self.player.height / 2.0,
+        );
+        for coin in &mut self.coins {
+            if magnet {
+                // Pull nearby coins towards the knight
+                let dx = center_x - (coin.x + Coin::SIZE / 2.0);
+                let dy = center_y - (coin.y + Coin::SIZE / 2.0);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance < MAGNET_RADIUS && distance > 0.0 {
+                    let step = (MAGNET_PULL * delta).min(distance);
+                    coin.x += dx / distance * step;
+                    coin.y += dy / distance * step;
+                }
+            }
+            if coin.check_collision(&self.player) {
+                coin.collected = true;
+                self.coins_collected += 1;
+                self.events.push(GameEvent::CoinCollected);
+            }
+        }
+
+        for checkpoint in &mut self.checkpoints {
+            if checkpoint.check_collision(&self.player) {
+                checkpoint.reached = true;
+                self.checkpoints_reached += 1;
+                self.respawn = Some(RespawnPoint {
+                    x: checkpoint.x,
+                    y: checkpoint.y,
+                    health: self.player.health,
+                });
+                self.events.push(GameEvent::CheckpointReached);
+            }
+        }
+
+        // Goblins felled this frame by the sword or thrown items
+        let mut defeated = Vec::new();
+
+        for projectile in &mut self.projectiles {
+            let detonated = projectile.update(delta, &self.platforms);
+            if detonated {
+                self.shake = SHAKE_TIME;
+                self.events.push(GameEvent::BombExploded { x: projectile.x, y: projectile.y });
+            }
+            for enemy in self.enemies.iter_mut().filter(|enemy| enemy.is_alive) {
+                let from_right = projectile.vel_x > 0.0;
+                if detonated {
+                    let dx = enemy.x + enemy.width / 2.0 - projectile.x;
+                    let dy = enemy.y + enemy.height / 2.0 - projectile.y;
+                    if dx * dx + dy * dy <= BOMB_RADIUS * BOMB_RADIUS {
+                        enemy.take_damage(dx < 0.0, BOMB_DAMAGE);
+                        self.events.push(GameEvent::EnemyHit { x: projectile.x + dx, y: projectile.y + dy });
+                    }
+                } else if projectile.kind == ItemKind::Dagger && !projectile.done && projectile.hits(enemy) {
+                    enemy.take_damage(from_right, 1);
+                    projectile.done = true;
+                    self.events.push(GameEvent::EnemyHit { x: projectile.x, y: projectile.y });
+                }
+                if !enemy.is_alive {
+                    defeated.push((enemy.x, enemy.y, enemy.kind));
                 }
             }
         }
+        self.projectiles.retain(|projectile| !projectile.done);
 
         // Check for sword hits during the swing (only in first half of animation)
         if self.player.is_attacking {
-            let swing_progress = 1.0 - (self.player.attack_cooldown / 0.4);
+            let swing_progress = self.player.swing_progress();
             if swing_progress < 0.5 {  // Only hit during the actual swing, not return
                 let (sx, sy, sw, sh) = self.player.get_sword_hitbox();
                 for enemy in &mut self.enemies {
@@ -685,39 +1949,90 @@ impl Game {
                            sx + sw > enemy.x &&
                            sy < enemy.y + enemy.height &&
                            sy + sh > enemy.y {
-                            console_log!("ENEMY DEFEATED!");
-                            enemy.take_damage(self.player.facing_right);
+                            enemy.take_damage(self.player.facing_right, self.player.sword_damage());
                             enemy.hit_flash = 1.0;  // Prevent multiple hits
+                            self.events.push(GameEvent::EnemyHit {
+                                x: enemy.x + enemy.width / 2.0,
+                                y: enemy.y + enemy.height / 2.0,
+                            });
+                            if !enemy.is_alive {
+                                defeated.push((enemy.x, enemy.y, enemy.kind));
+                            }
                         }
                     }
                 }
             }
         }
+        for (x, y, kind) in defeated {
+            self.enemy_defeated(x, y, kind);
+        }
 
         // Check for player-enemy collision and damage player
-        for enemy in &self.enemies {
+        for enemy in &mut self.enemies {
             if enemy.is_alive &&
+               enemy.stagger <= 0.0 &&
                !self.player.invincible &&
                self.player.x < enemy.x + enemy.width &&
                self.player.x + self.player.width > enemy.x &&
                self.player.y < enemy.y + enemy.height &&
                self.player.y + self.player.height > enemy.y {
-                // Damage and knockback player
-                self.player.take_damage();
-                
-                // Knockback player away from enemy
-                if self.player.x < enemy.x {
-                    self.player.vel_x = -8.0;
-                } else {
-                    self.player.vel_x = 8.0;
+                let enemy_from_right = enemy.x > self.player.x;
+                let outcome = self.player.receive_hit(
+                    enemy.x + enemy.width / 2.0,
+                    enemy.y + enemy.height / 2.0,
+                );
+
+                match outcome {
+                    HitOutcome::Parried => {
+                        self.events.push(GameEvent::ShieldBlocked { parried: true });
+                        enemy.stagger_for(1.5, !enemy_from_right, 10.0);
+                    }
+                    HitOutcome::Blocked => {
+                        self.events.push(GameEvent::ShieldBlocked { parried: false });
+                        // Both sides are pushed apart by the shield
+                        enemy.stagger_for(0.4, !enemy_from_right, 6.0);
+                        self.player.vel_x = if enemy_from_right { -3.0 } else { 3.0 };
+                    }
+                    HitOutcome::Damaged => {
+                        if self.modifiers.one_hit_death {
+                            // Whatever health is left goes with the hit
+                            self.player.health = 0.0;
+                            self.player.is_dead = true;
+                        }
+                        self.events.push(GameEvent::PlayerDamaged {
+                            quarters: self.player.hit_damage,
+                            health: self.player.health,
+                            x: self.player.x + self.player.width / 2.0,
+                            y: self.player.y + self.player.height / 2.0,
+                        });
+                        self.shake = SHAKE_TIME;
+                        // Brute clubs are smeared with poison
+                        if enemy.kind == EnemyKind::Brute {
+                            self.player.effects.grant(EffectKind::Poison);
+                        }
+
+                        // Knockback player away from enemy
+                        if self.player.x < enemy.x {
+                            self.player.vel_x = -8.0;
+                        } else {
+                            self.player.vel_x = 8.0;
+                        }
+                        self.player.vel_y = -5.0;
+                    }
+                    HitOutcome::Ignored => continue,
                 }
-                self.player.vel_y = -5.0;
-                
-                // Update hearts display immediately
+
                 break;  // Only take damage from one enemy at a time
             }
         }
 
+        // A spare life brings the knight back; only the last one ends the run
+        if self.player.is_dead && self.lives > 0 {
+            self.respawn();
+        } else if self.player.is_dead {
+            self.events.push(GameEvent::PlayerDied);
+        }
+
         // Update camera to follow player
         self.camera_x = self.player.x - 400.0;
         if self.camera_x < 0.0 {
@@ -726,26 +2041,60 @@ impl Game {
         
         // Track distance traveled
         if self.player.x > self.distance_traveled {
+            let before = (self.distance_traveled / 10.0) as u32;
             self.distance_traveled = self.player.x;
+            for meters in events::milestones_between(before, (self.distance_traveled / 10.0) as u32) {
+                self.events.push(GameEvent::DistanceMilestone { meters });
+            }
+        }
+
+        // Grant movement abilities as the knight progresses
+        for (meters, ability) in ABILITY_UNLOCKS {
+            if self.distance_traveled / 10.0 >= meters && self.player.abilities.unlock(ability) {
+                self.events.push(GameEvent::AbilityUnlocked { ability });
+                self.toast = Some((format!("{} UNLOCKED!", ability.name()), 2.5));
+            }
+        }
+        if let Some((_, time_left)) = &mut self.toast {
+            *time_left -= delta * 0.016;
+            if *time_left <= 0.0 {
+                self.toast = None;
+            }
         }
+        self.shake = (self.shake - delta * 0.016).max(0.0);
         
         // Generate new platforms ahead and cleanup behind
         self.generate_platforms_ahead();
         self.cleanup_behind();
+
+        self.score.update(delta * 0.016, (self.distance_traveled / 10.0) as u32);
+
+        for event in &self.events {
+            self.score.handle(event);
+            self.particles.handle(event);
+        }
+        self.particles.update(delta);
     }
 
-    fn render(&self, ctx: &CanvasRenderingContext2d) {
+    // Offset of the world while the screen shakes, easing out as it ends
+    fn shake_offset(&self) -> (f64, f64) {
+        let strength = SHAKE_STRENGTH * self.shake / SHAKE_TIME;
+        ((self.game_time * 1.7).sin() * strength, (self.game_time * 2.3).cos() * strength)
+    }
+
+    fn render(&self, ctx: &CanvasRenderingContext2d, screen_shake: bool) {
         ctx.set_image_smoothing_enabled(false);
         ctx.clear_rect(0.0, 0.0, 800.0, 500.0);
         
         // Draw sky gradient
-        ctx.set_fill_style(&JsValue::from_str("#87CEEB"));
+        ctx.set_fill_style_str("#87CEEB");
         ctx.fill_rect(0.0, 0.0, 800.0, 300.0);
-        ctx.set_fill_style(&JsValue::from_str("#98D8E8"));
+        ctx.set_fill_style_str("#98D8E8");
         ctx.fill_rect(0.0, 300.0, 800.0, 200.0);
 
         ctx.save();
-        ctx.translate(-self.camera_x, 0.0).unwrap();
+        let (shake_x, shake_y) = if screen_shake { self.shake_offset() } else { (0.0, 0.0) };
+        ctx.translate(-self.camera_x + shake_x, shake_y).unwrap();
 
         // Draw platforms with pixel art style
         for platform in &self.platforms {
@@ -758,42 +2107,156 @@ impl Game {
                 // Flash white when hit
                 if enemy.hit_flash > 0.0 {
                     ctx.set_global_alpha(0.8);
-                    ctx.set_fill_style(&JsValue::from_str("#FFFFFF"));
+                    ctx.set_fill_style_str("#FFFFFF");
                     ctx.fill_rect(enemy.x, enemy.y, enemy.width, enemy.height);
                     ctx.set_global_alpha(1.0);
                 } else {
-                    let goblin = create_goblin_sprite();
-                    goblin.draw(ctx, enemy.x, enemy.y, 3.0, enemy.vel_x < 0.0);
+                    match enemy.kind {
+                        EnemyKind::Goblin => create_goblin_sprite().draw(ctx, enemy.x, enemy.y, 3.0, enemy.vel_x < 0.0),
+                        EnemyKind::Brute => create_brute_sprite().draw(ctx, enemy.x, enemy.y, 4.0, enemy.vel_x < 0.0),
+                    }
+
+                    // Dizzy stars while staggered
+                    if enemy.stagger > 0.0 {
+                        ctx.set_fill_style_str("#FFFF00");
+                        let spin = self.game_time * 0.2;
+                        for i in 0..3 {
+                            let angle = spin + i as f64 * 2.094;
+                            ctx.fill_rect(
+                                enemy.x + enemy.width / 2.0 + angle.cos() * 10.0 - 2.0,
+                                enemy.y - 6.0 + angle.sin() * 3.0,
+                                4.0,
+                                4.0,
+                            );
+                        }
+                    }
                 }
             }
         }
         
-        // Draw heart pickups
-        for heart in &self.hearts {
-            if !heart.collected {
-                let float_y = heart.y + heart.float_offset;
+        for coin in &self.coins {
+            if !coin.collected {
+                draw_pixel_coin(ctx, coin.x, coin.y, self.game_time);
+            }
+        }
+
+        for checkpoint in &self.checkpoints {
+            draw_checkpoint_banner(ctx, checkpoint.x, checkpoint.y, checkpoint.reached);
+        }
+
+        // Draw pickups
+        for pickup in &self.pickups {
+            if !pickup.collected {
+                let float_y = pickup.y + pickup.float_offset;
+                let glow = match pickup.kind {
+                    PickupKind::Heart => "#FF69B4",
+                    PickupKind::HeartContainer => "#FFD700",
+                    PickupKind::PowerUp(effect) => effect.icon().1,
+                    PickupKind::Item(_) => "#F5DEB3",
+                };
                 
                 // Draw glowing effect
-                ctx.set_fill_style(&JsValue::from_str("#FF69B4"));
+                ctx.set_fill_style_str(glow);
                 ctx.set_global_alpha(0.3);
                 ctx.begin_path();
-                ctx.arc(heart.x + 10.0, float_y + 10.0, 15.0, 0.0, std::f64::consts::PI * 2.0).unwrap();
+                ctx.arc(pickup.x + 10.0, float_y + 10.0, 15.0, 0.0, std::f64::consts::PI * 2.0).unwrap();
                 ctx.fill();
                 ctx.set_global_alpha(1.0);
                 
-                // Draw the pixel heart sprite
-                draw_pixel_heart(ctx, heart.x, float_y, 3.0);
+                match pickup.kind {
+                    PickupKind::Heart => draw_pixel_heart(ctx, pickup.x, float_y, 3.0),
+                    PickupKind::HeartContainer => {
+                        ctx.set_stroke_style_str("#FFD700");
+                        ctx.set_line_width(2.0);
+                        ctx.stroke_rect(pickup.x - 2.0, float_y - 2.0, 25.0, 22.0);
+                        draw_pixel_heart(ctx, pickup.x, float_y, 3.0);
+                    }
+                    PickupKind::PowerUp(effect) => draw_power_up(ctx, pickup.x, float_y, effect),
+                    PickupKind::Item(item) => draw_item_icon(ctx, pickup.x, float_y, item),
+                }
+            }
+        }
+
+        for projectile in &self.projectiles {
+            match (projectile.kind, projectile.explosion) {
+                (_, Some(elapsed)) => {
+                    let progress = elapsed / EXPLOSION_TIME;
+                    ctx.set_global_alpha(1.0 - progress * 0.8);
+                    ctx.set_fill_style_str("#FF8C00");
+                    ctx.begin_path();
+                    ctx.arc(projectile.x, projectile.y, BOMB_RADIUS * (0.4 + progress * 0.6), 0.0, std::f64::consts::PI * 2.0).unwrap();
+                    ctx.fill();
+                    ctx.set_fill_style_str("#FFFF66");
+                    ctx.begin_path();
+                    ctx.arc(projectile.x, projectile.y, BOMB_RADIUS * 0.3, 0.0, std::f64::consts::PI * 2.0).unwrap();
+                    ctx.fill();
+                    ctx.set_global_alpha(1.0);
+                }
+                (ItemKind::Bomb, None) => {
+                    draw_item_icon(ctx, projectile.x - 10.0, projectile.y - 10.0, ItemKind::Bomb);
+                }
+                (_, None) => {
+                    // Spinning is implied by flipping the blade each few frames
+                    let flip = (self.game_time * 0.5) as i32 % 2 == 0;
+                    ctx.set_fill_style_str("#C0C0C0");
+                    if flip {
+                        ctx.fill_rect(projectile.x - 8.0, projectile.y - 1.5, 16.0, 3.0);
+                    } else {
+                        ctx.fill_rect(projectile.x - 1.5, projectile.y - 8.0, 3.0, 16.0);
+                    }
+                    ctx.set_fill_style_str("#8B4513");
+                    ctx.fill_rect(projectile.x - 2.0, projectile.y - 2.0, 4.0, 4.0);
+                }
             }
         }
 
 
+        let (center_x, center_y) = (
+            self.player.x + self.player.width / 2.0,
+            self.player.y + self.player.height / 2.0,
+        );
+        if self.player.effects.has(EffectKind::Invincibility) {
+            // Pulsing golden aura
+            ctx.set_fill_style_str("#FFFF66");
+            ctx.set_global_alpha(0.25 + (self.game_time * 0.3).sin().abs() * 0.25);
+            ctx.begin_path();
+            ctx.arc(center_x, center_y, 26.0, 0.0, std::f64::consts::PI * 2.0).unwrap();
+            ctx.fill();
+            ctx.set_global_alpha(1.0);
+        }
+        if self.player.effects.has(EffectKind::Shield) {
+            ctx.set_stroke_style_str("#00CED1");
+            ctx.set_line_width(2.0);
+            ctx.set_global_alpha(0.7);
+            ctx.begin_path();
+            ctx.arc(center_x, center_y, 24.0, 0.0, std::f64::consts::PI * 2.0).unwrap();
+            ctx.stroke();
+            ctx.set_global_alpha(1.0);
+        }
+
         // Draw player with flashing when invincible
         if self.player.invincible && (self.player.damage_cooldown * 10.0) as i32 % 2 == 0 {
             // Flash effect - skip drawing every other frame
         } else {
             // Draw player as pixel knight
-            let frame = self.player.animation_frame as usize;
-            let knight = create_knight_sprite(frame);
+            // Afterimages trail behind a dash
+            if self.player.dash_timer > 0.0 {
+                let knight = create_knight_sprite(0);
+                ctx.set_global_alpha(0.3);
+                for i in 1..3 {
+                    let offset = self.player.vel_x * i as f64 * 1.5;
+                    knight.draw(ctx, self.player.x - offset, self.player.y, 3.0, !self.player.facing_right);
+                }
+                ctx.set_global_alpha(1.0);
+            }
+
+            let knight = if self.player.is_blocking {
+                create_knight_block_sprite(self.player.parry_timer > 0.0)
+            } else if self.player.is_crouching {
+                create_knight_crouch_sprite()
+            } else {
+                create_knight_sprite(self.player.animation_frame as usize)
+            };
             knight.draw(ctx, self.player.x, self.player.y, 3.0, !self.player.facing_right);
         }
         
@@ -807,7 +2270,7 @@ impl Game {
         
         // Draw sword based on attack state
         if self.player.is_attacking {
-            let swing_progress = 1.0 - (self.player.attack_cooldown / 0.4);
+            let swing_progress = self.player.swing_progress();
             
             // Sword rotates around the hand position (90 degrees to -10 degrees)
             let start_angle = 90.0_f64.to_radians();  // Start vertical
@@ -845,7 +2308,7 @@ impl Game {
                     let ty = hand_y - trail_angle.sin() * sword_length;
                     
                     // Draw simple line for trail
-                    ctx.set_stroke_style(&JsValue::from_str("#C0C0C0"));
+                    ctx.set_stroke_style_str("#C0C0C0");
                     ctx.set_line_width(3.0);
                     ctx.begin_path();
                     ctx.move_to(hand_x, hand_y);
@@ -856,7 +2319,7 @@ impl Game {
             }
             
             // Draw sword as a line from hand to tip
-            ctx.set_stroke_style(&JsValue::from_str("#C0C0C0"));
+            ctx.set_stroke_style_str("#C0C0C0");
             ctx.set_line_width(4.0);
             ctx.begin_path();
             ctx.move_to(hand_x, hand_y);
@@ -864,7 +2327,7 @@ impl Game {
             ctx.stroke();
             
             // Draw sword blade highlight
-            ctx.set_stroke_style(&JsValue::from_str("#FFFFFF"));
+            ctx.set_stroke_style_str("#FFFFFF");
             ctx.set_line_width(2.0);
             ctx.begin_path();
             ctx.move_to(hand_x, hand_y);
@@ -872,12 +2335,12 @@ impl Game {
             ctx.stroke();
             
             // Draw handle at pivot point
-            ctx.set_fill_style(&JsValue::from_str("#8B4513"));
+            ctx.set_fill_style_str("#8B4513");
             ctx.fill_rect(hand_x - 3.0, hand_y - 3.0, 6.0, 6.0);
             
             // Add impact effect at peak of swing
             if swing_progress > 0.4 && swing_progress < 0.6 {
-                ctx.set_stroke_style(&JsValue::from_str("#FFFF00"));
+                ctx.set_stroke_style_str("#FFFF00");
                 ctx.set_global_alpha(0.6);
                 ctx.set_line_width(8.0);
                 ctx.begin_path();
@@ -887,82 +2350,1544 @@ impl Game {
                 ctx.set_global_alpha(1.0);
             }
             
+        } else if self.player.is_blocking {
+            // Sword lowered behind the shield
+            let sword = create_sword_sprite_horizontal();
+            let lowered_x = if self.player.facing_right {
+                self.player.x - 10.0
+            } else {
+                self.player.x + self.player.width - 6.0
+            };
+            sword.draw(ctx, lowered_x, self.player.y + 18.0, 2.0, !self.player.facing_right);
         } else {
             // Draw sword at rest (vertical)
             let sword = create_sword_sprite_vertical();
             sword.draw(ctx, sword_base_x, sword_base_y - 8.0, 2.0, !self.player.facing_right);
         }
 
+        if self.player.effects.has(EffectKind::FlamingSword) {
+            // Flickering flames over the blade
+            let (fx, fy, fw, fh) = if self.player.is_attacking {
+                self.player.get_sword_hitbox()
+            } else {
+                (sword_base_x - 2.0, sword_base_y - 14.0, 10.0, 20.0)
+            };
+            for i in 0..6 {
+                let flicker = (self.game_time * 0.5 + i as f64 * 1.7).sin();
+                ctx.set_fill_style_str(if i % 2 == 0 { "#FF4500" } else { "#FFD700" });
+                ctx.set_global_alpha(0.6);
+                ctx.fill_rect(
+                    fx + (i as f64 / 6.0) * fw,
+                    fy + fh * (0.5 + flicker * 0.4) - 3.0,
+                    4.0,
+                    4.0,
+                );
+            }
+            ctx.set_global_alpha(1.0);
+        }
+
+        self.particles.render(ctx);
+
         ctx.restore();
 
-        // Draw 7 hearts with quarter heart precision
-        let total_quarters = self.player.health as i32;
-        for i in 0..7 {
-            let heart_quarters = (total_quarters - (i * 4)).min(4).max(0);
-            draw_heart_quarters(ctx, 10.0 + i as f64 * 22.0, 10.0, heart_quarters);
+        // Draw hearts with quarter heart precision, wrapping into rows
+        // A partial quarter left by a light hit still shows
+        let total_quarters = self.player.health.ceil() as i32;
+        let heart_count = (self.player.max_health / 4.0).ceil() as i32;
+        let poisoned = self.player.effects.has(EffectKind::Poison);
+        for i in 0..heart_count {
+            let heart_quarters = (total_quarters - (i * 4)).clamp(0, 4);
+            let x = 10.0 + (i % HEARTS_PER_ROW) as f64 * 22.0;
+            let y = 10.0 + (i / HEARTS_PER_ROW) as f64 * 20.0;
+            draw_heart_quarters(ctx, x, y, heart_quarters);
+            if poisoned && heart_quarters > 0 {
+                ctx.set_global_alpha(0.45);
+                ctx.set_fill_style_str("#9ACD32");
+                ctx.fill_rect(x, y, 21.0, 18.0);
+                ctx.set_global_alpha(1.0);
+            }
         }
-        
-        // Show game over message
-        if self.player.is_dead {
-            ctx.set_fill_style(&JsValue::from_str("#FF0000"));
-            ctx.set_font("48px Arial");
-            ctx.fill_text("GAME OVER", 250.0, 250.0).unwrap();
+        // Everything else on the left stacks below the heart rows
+        let rows = ((heart_count + HEARTS_PER_ROW - 1) / HEARTS_PER_ROW).max(1);
+        let top = 14.0 + rows as f64 * 20.0;
+
+        // Stamina bar under the hearts
+        let stamina_ratio = self.player.stamina / self.player.max_stamina;
+        ctx.set_fill_style_str("#333333");
+        ctx.fill_rect(10.0, top, 150.0, 6.0);
+        ctx.set_fill_style_str(if stamina_ratio < BLOCK_STAMINA_COST / self.player.max_stamina {
+            "#B22222"
+        } else {
+            "#32CD32"
+        });
+        ctx.fill_rect(10.0, top, 150.0 * stamina_ratio, 6.0);
+
+        // Level and XP towards the next one
+        let mut hud = Ui::new();
+        hud.bar(Rect::new(10.0, top + 8.0, 150.0, 3.0), self.player.experience.progress(), "#9370DB", "#333333")
+            .label(ui::SCREEN, Anchor::TopLeft, 166.0, top + 6.0,
+                &format!("Lv {}", self.player.experience.level), 1.0, ui::WHITE)
+            .label(ui::SCREEN, Anchor::TopLeft, 10.0, top + 16.0,
+                &format!("Checkpoints: {}", self.checkpoints_reached), 1.0, ui::WHITE)
+            .label(Rect::new(10.0, top + 32.0, 12.0, 12.0), Anchor::Left, 18.0, 0.0,
+                &self.coins_collected.to_string(), 2.0, ui::GOLD);
+        if self.mode == RunMode::Lives {
+            hud.label(ui::SCREEN, Anchor::TopLeft, 120.0, top + 16.0, &format!("Lives: {}", self.lives), 1.0, ui::WHITE);
         }
+        if self.mode == RunMode::Daily {
+            // Under the inventory slots
+            hud.label(ui::SCREEN, Anchor::Top, 0.0, 46.0, &self.modifiers.names().join(", "), 1.0, ui::GREY);
+        }
+        draw_pixel_coin(ctx, 10.0, top + 32.0, 0.0);
         
-        // Show distance traveled
-        ctx.set_fill_style(&JsValue::from_str("#FFFFFF"));
-        ctx.set_font("16px Arial");
-        ctx.fill_text(&format!("Distance: {}m", (self.distance_traveled / 10.0) as i32), 650.0, 30.0).unwrap();
+        // Inventory slots across the top, the selected one outlined in gold
+        for (i, slot) in self.player.inventory.slots().iter().enumerate() {
+            let x = 349.0 + i as f64 * 34.0;
+            ctx.set_global_alpha(0.6);
+            ctx.set_fill_style_str("#2E3440");
+            ctx.fill_rect(x, 8.0, 30.0, 30.0);
+            ctx.set_global_alpha(1.0);
+            let selected = i == self.player.inventory.selected();
+            ctx.set_stroke_style_str(if selected { "#FFD700" } else { "#808080" });
+            ctx.set_line_width(if selected { 2.0 } else { 1.0 });
+            ctx.stroke_rect(x, 8.0, 30.0, 30.0);
+            if let Some(stack) = slot {
+                draw_item_icon(ctx, x + 5.0, 13.0, stack.kind);
+                if stack.count > 1 {
+                    let slot = Rect::new(x, 8.0, 30.0, 30.0);
+                    hud.label(slot, Anchor::BottomRight, -2.0, -2.0, &stack.count.to_string(), 1.0, ui::WHITE);
+                }
+            }
+        }
+
+        // Active power-ups with the seconds they have left
+        for (i, effect) in self.player.effects.iter().enumerate() {
+            let x = 10.0 + i as f64 * 44.0;
+            let (letter, color) = effect.kind.icon();
+            ctx.set_fill_style_str("#2E3440");
+            ctx.fill_rect(x, top + 50.0, 18.0, 18.0);
+            ctx.set_fill_style_str(color);
+            ctx.fill_rect(x + 2.0, top + 52.0, 14.0, 14.0);
+            font::draw_text(ctx, letter, x + 7.0, top + 56.0, 1.0, "#000000");
+            hud.label(ui::SCREEN, Anchor::TopLeft, x + 21.0, top + 56.0,
+                &effect.remaining.ceil().to_string(), 1.0, ui::WHITE);
+        }
+
+        if let Some((message, _)) = &self.toast {
+            hud.label(ui::SCREEN, Anchor::Top, 0.0, 110.0, message, 2.0, ui::GOLD);
+        }
+
+        // Distance, score and combo line up against the right edge
+        hud.label(ui::SCREEN, Anchor::TopRight, -10.0, 12.0,
+                &format!("Distance: {}m", (self.distance_traveled / 10.0) as i32), 2.0, ui::WHITE)
+            .label(ui::SCREEN, Anchor::TopRight, -10.0, 32.0, &format!("Score: {}", self.score.score()), 2.0, ui::WHITE);
+        if self.score.combo() > 1 {
+            hud.label(ui::SCREEN, Anchor::TopRight, -10.0, 54.0,
+                &format!("x{:.1} COMBO", self.score.multiplier()), 3.0, ui::GOLD);
+        }
+        hud.render(ctx);
     }
 }
 
+// Hears every game event; on_event wraps the page's function in one
+type EventListener = Rc<dyn Fn(&GameEvent)>;
+
 #[wasm_bindgen]
 pub struct GameEngine {
     game: Rc<RefCell<Game>>,
+    scene: Rc<RefCell<Scene>>,
+    input: Rc<RefCell<Input>>,
+    gamepad_mapping: GamepadMapping,
+    touch: Rc<RefCell<TouchControls>>,
+    storage: Rc<RefCell<Box<dyn Storage>>>,
+    high_scores: Rc<RefCell<HighScoreTable>>,
+    player_name: Rc<RefCell<String>>,
+    progress: Rc<RefCell<Progress>>,
+    settings: Rc<RefCell<Settings>>,
+    fps: Rc<RefCell<f64>>,  // Smoothed frame rate for the FPS display
+    audio: Rc<RefCell<AudioPlayer>>,
+    event_callback: Rc<RefCell<Option<EventListener>>>,  // Set by the page with on_event
+    achievements: Rc<RefCell<Achievements>>,
+    daily: Rc<RefCell<Option<DailyChallenge>>>,  // Today's challenge, once the page passes the date
 }
 
-#[wasm_bindgen]
+const PLAYER_NAME_KEY: &str = "playerName";
+
+// Today's date as YYYY-MM-DD
+#[cfg(target_arch = "wasm32")]
+fn today() -> String {
+    let iso = String::from(js_sys::Date::new_0().to_iso_string());
+    iso.chars().take(10).collect()
+}
+
+// Native builds (unit tests) have no JS clock to ask
+#[cfg(not(target_arch = "wasm32"))]
+fn today() -> String {
+    "1970-01-01".to_string()
+}
+
+impl Default for GameEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
 impl GameEngine {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         console_log!("Game engine initialized!");
+        let storage: Box<dyn Storage> = match LocalStorage::new() {
+            Some(local) => Box::new(local),
+            None => Box::new(MemoryStorage::default()),
+        };
+        Self::with_storage(storage)
+    }
+
+    fn with_storage(storage: Box<dyn Storage>) -> Self {
+        let high_scores = HighScoreTable::load(storage.as_ref());
+        let player_name = storage.get(PLAYER_NAME_KEY).unwrap_or_else(|| "Knight".to_string());
+        let progress = Progress::load(storage.as_ref());
+        let settings = Settings::load(storage.as_ref());
+        let achievements = Achievements::load(storage.as_ref());
+        let mut input = Input::default();
+        input.set_key_map(settings.key_map.clone());
+        let mut game = Game::new(random_seed(), RunMode::Standard, &progress.upgrades, settings.difficulty);
+        game.set_assists(settings.assists);
         GameEngine {
-            game: Rc::new(RefCell::new(Game::new())),
+            game: Rc::new(RefCell::new(game)),
+            scene: Rc::new(RefCell::new(Scene::Title { selected: scene::TITLE_START })),
+            input: Rc::new(RefCell::new(input)),
+            gamepad_mapping: GamepadMapping::default(),
+            touch: Rc::new(RefCell::new(TouchControls::default())),
+            storage: Rc::new(RefCell::new(storage)),
+            high_scores: Rc::new(RefCell::new(high_scores)),
+            player_name: Rc::new(RefCell::new(player_name)),
+            progress: Rc::new(RefCell::new(progress)),
+            settings: Rc::new(RefCell::new(settings)),
+            fps: Rc::new(RefCell::new(60.0)),
+            audio: Rc::new(RefCell::new(AudioPlayer::default())),
+            event_callback: Rc::new(RefCell::new(None)),
+            achievements: Rc::new(RefCell::new(achievements)),
+            daily: Rc::new(RefCell::new(None)),
         }
     }
 
     pub fn update(&self, delta: f64) {
-        self.game.borrow_mut().update(delta);
+        self.poll_gamepads();
+        if delta > 0.0 {
+            // delta is in 60 Hz frames
+            let mut fps = self.fps.borrow_mut();
+            *fps += (60.0 / delta - *fps) * 0.1;
+        }
+        self.achievements.borrow_mut().update(delta * 0.016);
+        let keys = self.input.borrow_mut().tick().clone();
+        self.advance(delta, &keys);
+    }
+
+    // Scene flow and simulation for one tick of input. Split from update,
+    // which also polls the browser's gamepads, so tests can drive it.
+    fn advance(&self, delta: f64, keys: &KeyState) {
+        let confirm = keys.pressed(Action::Confirm) || keys.pressed(Action::Attack);
+
+        let scene = *self.scene.borrow();
+        let next = match scene {
+            Scene::Title { selected } => match ui::navigate(selected, scene::TITLE_ITEMS, keys) {
+                Nav::Activate(scene::TITLE_START) => Scene::Playing,
+                Nav::Activate(scene::TITLE_SHOP) => Scene::Shop { selected: 0, flash: 0.0 },
+                Nav::Activate(scene::TITLE_LEADERBOARD) => Scene::Leaderboard,
+                Nav::Activate(scene::TITLE_ACHIEVEMENTS) => Scene::Achievements,
+                Nav::Activate(scene::TITLE_OPTIONS) => Scene::Options { selected: 0, from_pause: false },
+                Nav::Focus(selected) => Scene::Title { selected },
+                _ if selected == scene::TITLE_MODE
+                    && (keys.pressed(Action::Left) || keys.pressed(Action::Right)) => {
+                    // The run behind the title is the one that gets played.
+                    // Daily is only offered once the page has passed a date.
+                    let mut mode = self.game.borrow().mode.toggled();
+                    if mode == RunMode::Daily && self.daily.borrow().is_none() {
+                        mode = mode.toggled();
+                    }
+                    self.replace_run(true, mode);
+                    scene
+                }
+                _ => scene,
+            },
+            Scene::Shop { selected, flash } => match ui::navigate(selected, shop::UPGRADES.len(), keys) {
+                Nav::Back => {
+                    // Rebuild the waiting run so new upgrades take effect
+                    let mode = self.game.borrow().mode;
+                    self.replace_run(true, mode);
+                    Scene::Title { selected: scene::TITLE_SHOP }
+                }
+                Nav::Activate(selected) => {
+                    let mut progress = self.progress.borrow_mut();
+                    if progress.buy(selected).is_ok() {
+                        progress.save(self.storage.borrow_mut().as_mut());
+                        Scene::Shop { selected, flash: 0.0 }
+                    } else {
+                        // The overlay shows why for a moment
+                        Scene::Shop { selected, flash: scene::SHOP_FLASH_TIME }
+                    }
+                }
+                Nav::Focus(selected) => Scene::Shop { selected, flash: 0.0 },
+                Nav::Stay => Scene::Shop { selected, flash: (flash - delta * 0.016).max(0.0) },
+            },
+            Scene::Leaderboard => {
+                if confirm || keys.pressed(Action::Pause) || keys.pressed(Action::Jump) {
+                    Scene::Title { selected: scene::TITLE_LEADERBOARD }
+                } else {
+                    Scene::Leaderboard
+                }
+            }
+            Scene::Achievements => {
+                if confirm || keys.pressed(Action::Pause) || keys.pressed(Action::Jump) {
+                    Scene::Title { selected: scene::TITLE_ACHIEVEMENTS }
+                } else {
+                    Scene::Achievements
+                }
+            }
+            Scene::Playing => {
+                if keys.pressed(Action::Pause) {
+                    Scene::Paused { selected: scene::PAUSE_RESUME }
+                } else {
+                    let (events, summary, assisted, next) = {
+                        let mut game = self.game.borrow_mut();
+                        game.update(delta, keys);
+                        let next = if game.player.is_dead {
+                            Scene::GameOver { elapsed: 0.0 }
+                        } else if game.player.experience.pending > 0 {
+                            Scene::LevelUp { selected: 0, elapsed: 0.0 }
+                        } else {
+                            Scene::Playing
+                        };
+                        let assisted = game.assists_used.any();
+                        (std::mem::take(&mut game.events), game.score.summary(), assisted, next)
+                    };
+                    // The game is released and the scene settled first, as the
+                    // page's callback may query either, or pause or load a run.
+                    // Whatever scene it leaves behind is kept.
+                    *self.scene.borrow_mut() = next;
+                    self.drain_events(events, &summary, assisted);
+                    *self.scene.borrow()
+                }
+            }
+            // The run is frozen until every pending level-up has a stat picked
+            Scene::LevelUp { selected, elapsed } => {
+                // Left / Right move the focus too, as the choices read like a row on a pad
+                let count = StatChoice::ALL.len();
+                let elapsed = elapsed + delta * 0.016;
+                let nav = if elapsed < scene::LEVEL_UP_INPUT_DELAY {
+                    Nav::Stay
+                } else if keys.pressed(Action::Left) {
+                    Nav::Focus((selected + count - 1) % count)
+                } else if keys.pressed(Action::Right) {
+                    Nav::Focus((selected + 1) % count)
+                } else {
+                    ui::navigate(selected, count, keys)
+                };
+                match nav {
+                    Nav::Activate(selected) => {
+                        let mut game = self.game.borrow_mut();
+                        let choice = StatChoice::ALL[selected];
+                        choice.apply(&mut game.player);
+                        game.player.experience.pending -= 1;
+                        if game.player.experience.pending > 0 {
+                            Scene::LevelUp { selected: 0, elapsed: 0.0 }
+                        } else {
+                            Scene::Playing
+                        }
+                    }
+                    Nav::Focus(selected) => Scene::LevelUp { selected, elapsed },
+                    Nav::Back | Nav::Stay => Scene::LevelUp { selected, elapsed },
+                }
+            }
+            // The world stays frozen but is still drawn underneath
+            Scene::Paused { selected } => match ui::navigate(selected, scene::PAUSE_ITEMS, keys) {
+                Nav::Activate(scene::PAUSE_OPTIONS) => Scene::Options { selected: 0, from_pause: true },
+                Nav::Activate(_) | Nav::Back => Scene::Playing,
+                Nav::Focus(selected) => Scene::Paused { selected },
+                Nav::Stay => scene,
+            },
+            Scene::Options { selected, from_pause } => {
+                let item = OptionItem::ALL[selected];
+                match ui::navigate(selected, OptionItem::ALL.len(), keys) {
+                    Nav::Focus(selected) => Scene::Options { selected, from_pause },
+                    Nav::Activate(_) if item == OptionItem::Controls => {
+                        Scene::Controls { selected: 0, listening: false, from_pause }
+                    }
+                    Nav::Activate(_) if item != OptionItem::Back => {
+                        self.change_setting(item, 1);
+                        scene
+                    }
+                    Nav::Activate(_) | Nav::Back => {
+                        if from_pause {
+                            Scene::Paused { selected: scene::PAUSE_OPTIONS }
+                        } else {
+                            // The waiting run picks up a new difficulty
+                            let mode = self.game.borrow().mode;
+                            self.replace_run(true, mode);
+                            Scene::Title { selected: scene::TITLE_OPTIONS }
+                        }
+                    }
+                    Nav::Stay => {
+                        if keys.pressed(Action::Left) {
+                            self.change_setting(item, -1);
+                        } else if keys.pressed(Action::Right) {
+                            self.change_setting(item, 1);
+                        }
+                        scene
+                    }
+                }
+            }
+            Scene::Controls { selected, listening: true, from_pause } => {
+                let captured = self.input.borrow_mut().take_captured();
+                match captured {
+                    // Escape cancels rather than being bound
+                    Some(key) => {
+                        if key != "Escape" {
+                            let action = Action::ALL[selected];
+                            self.settings.borrow_mut().key_map.rebind(action, &key);
+                            self.apply_key_map();
+                        }
+                        Scene::Controls { selected, listening: false, from_pause }
+                    }
+                    None => scene,
+                }
+            }
+            Scene::Controls { selected, listening: false, from_pause } => {
+                match ui::navigate(selected, scene::CONTROLS_ITEMS, keys) {
+                    Nav::Focus(selected) => Scene::Controls { selected, listening: false, from_pause },
+                    Nav::Activate(scene::CONTROLS_RESET) => {
+                        self.settings.borrow_mut().key_map = KeyMap::default();
+                        self.apply_key_map();
+                        scene
+                    }
+                    Nav::Activate(scene::CONTROLS_BACK) | Nav::Back => {
+                        let selected = OptionItem::ALL.iter().position(|item| *item == OptionItem::Controls).unwrap_or(0);
+                        Scene::Options { selected, from_pause }
+                    }
+                    Nav::Activate(selected) => {
+                        self.input.borrow_mut().listen();
+                        Scene::Controls { selected, listening: true, from_pause }
+                    }
+                    Nav::Stay => scene,
+                }
+            }
+            Scene::GameOver { elapsed } => {
+                let elapsed = elapsed + delta * 0.016;
+                if elapsed >= GAME_OVER_DELAY || (elapsed > 0.5 && confirm) {
+                    Scene::Results { rank: self.record_run(), selected: scene::RESULTS_NEW_RUN }
+                } else {
+                    Scene::GameOver { elapsed }
+                }
+            }
+            Scene::Results { rank, selected } => match ui::navigate(selected, scene::RESULTS_ITEMS, keys) {
+                Nav::Activate(scene::RESULTS_RETRY) => {
+                    self.restart(true);
+                    Scene::Playing
+                }
+                Nav::Activate(scene::RESULTS_TITLE) | Nav::Back => {
+                    self.restart(false);
+                    Scene::Title { selected: scene::TITLE_START }
+                }
+                Nav::Activate(_) => {
+                    self.restart(false);
+                    Scene::Playing
+                }
+                Nav::Focus(selected) => Scene::Results { rank, selected },
+                Nav::Stay => scene,
+            },
+        };
+        *self.scene.borrow_mut() = next;
+        self.update_music();
+    }
+
+    fn update_music(&self) {
+        let tune = match *self.scene.borrow() {
+            Scene::GameOver { .. } | Scene::Results { .. } => None,
+            Scene::Playing
+            | Scene::Paused { .. }
+            | Scene::LevelUp { .. }
+            | Scene::Options { from_pause: true, .. }
+            | Scene::Controls { from_pause: true, .. } => Some(self.game.borrow().tune()),
+            _ => Some((Track::Title, false)),
+        };
+        let volume = self.settings.borrow().music_volume as f64 / VOLUME_STEPS as f64;
+        self.audio.borrow_mut().play_music(tune, volume);
     }
 
     pub fn render(&self, ctx: &CanvasRenderingContext2d) {
-        self.game.borrow().render(ctx);
+        let game = self.game.borrow();
+        let settings = self.settings.borrow();
+        game.render(ctx, settings.screen_shake);
+        self.scene.borrow().render_overlay(
+            ctx,
+            &game,
+            &self.high_scores.borrow(),
+            &self.progress.borrow(),
+            &settings,
+            &self.achievements.borrow(),
+        );
+        self.touch.borrow().render(ctx);
+        if settings.show_fps {
+            let fps = format!("{:.0} FPS", *self.fps.borrow());
+            let mut ui = Ui::new();
+            ui.label(ui::SCREEN, Anchor::BottomRight, -8.0, -8.0, &fps, 1.0, ui::WHITE).render(ctx);
+        }
+        self.achievements.borrow().render_toast(ctx);
+    }
+
+    // Swap in a fresh run, optionally replaying the current level layout
+    pub fn restart(&self, same_seed: bool) {
+        let mode = self.game.borrow().mode;
+        self.replace_run(same_seed, mode);
+        *self.scene.borrow_mut() = Scene::Playing;
+    }
+
+    // New run with the current upgrades, without changing scene
+    fn replace_run(&self, same_seed: bool, mode: RunMode) {
+        let (seed, modifiers) = if mode == RunMode::Daily {
+            // Always the day's run; a restored save may predate the date
+            match &*self.daily.borrow() {
+                Some(daily) => (daily.seed, daily.modifiers),
+                None => {
+                    let game = self.game.borrow();
+                    (game.seed, game.modifiers)
+                }
+            }
+        } else if same_seed {
+            (self.game.borrow().seed, Modifiers::default())
+        } else {
+            (random_seed(), Modifiers::default())
+        };
+        let settings = self.settings.borrow();
+        let difficulty = if mode == RunMode::Daily { DAILY_DIFFICULTY } else { settings.difficulty };
+        let upgrades = &self.progress.borrow().upgrades;
+        let mut game = Game::with_modifiers(seed, mode, upgrades, difficulty, modifiers);
+        game.set_assists(settings.assists);
+        *self.game.borrow_mut() = game;
+    }
+
+    // Saves the finished run, banks its coins and returns its rank on the
+    // run's board
+    fn record_run(&self) -> Option<usize> {
+        let game = self.game.borrow();
+        let mut progress = self.progress.borrow_mut();
+        progress.coins += game.coins_collected as u64;
+        progress.save(self.storage.borrow_mut().as_mut());
+
+        let summary = game.score.summary();
+        let entry = HighScoreEntry {
+            name: self.player_name.borrow().clone(),
+            score: summary.score,
+            distance: summary.distance,
+            date: today(),
+            seed: game.seed,
+            mode: game.mode.name().to_string(),
+            difficulty: game.difficulty.id().to_string(),
+            assists: game.assists_used.names(),
+        };
+
+        let mut high_scores = self.high_scores.borrow_mut();
+        let rank = high_scores.submit(entry);
+        high_scores.save(self.storage.borrow_mut().as_mut());
+        rank
     }
 
-    pub fn key_down(&self, key: String) {
-        let mut game = self.game.borrow_mut();
-        match key.as_str() {
-            "ArrowLeft" | "a" | "A" => game.keys.left = true,
-            "ArrowRight" | "d" | "D" => game.keys.right = true,
-            "ArrowUp" | "w" | "W" => game.keys.up = true,
-            " " => game.keys.space = true,
-            _ => {}
+    // Sets up the daily challenge for a YYYY-MM-DD date, normally the
+    // player's local date. Modes cycle through Daily once this is called.
+    pub fn set_daily_date(&self, date: String) -> Result<(), JsValue> {
+        let daily = DailyChallenge::for_date(&date).map_err(|e| JsValue::from_str(&e))?;
+        *self.daily.borrow_mut() = Some(daily);
+        // A daily run waiting on the title moves on to the new day
+        let waiting = matches!(*self.scene.borrow(), Scene::Title { .. })
+            && self.game.borrow().mode == RunMode::Daily;
+        if waiting {
+            self.replace_run(true, RunMode::Daily);
         }
+        Ok(())
     }
 
-    pub fn key_up(&self, key: String) {
-        let mut game = self.game.borrow_mut();
-        match key.as_str() {
-            "ArrowLeft" | "a" | "A" => game.keys.left = false,
-            "ArrowRight" | "d" | "D" => game.keys.right = false,
-            "ArrowUp" | "w" | "W" => game.keys.up = false,
-            " " => game.keys.space = false,
-            _ => {}
+    pub fn set_player_name(&self, name: String) {
+        let name: String = name.trim().chars().take(12).collect();
+        if name.is_empty() {
+            return;
         }
+        self.storage.borrow_mut().set(PLAYER_NAME_KEY, &name);
+        *self.player_name.borrow_mut() = name;
+    }
+
+    pub fn export_high_scores(&self) -> String {
+        self.high_scores.borrow().to_json()
+    }
+
+    pub fn import_high_scores(&self, json: String) -> Result<(), JsValue> {
+        let table = HighScoreTable::from_json(&json).map_err(|e| JsValue::from_str(&e))?;
+        table.save(self.storage.borrow_mut().as_mut());
+        *self.high_scores.borrow_mut() = table;
+        Ok(())
+    }
+
+    // Snapshot of the whole run, resumable with load_state
+    pub fn save_state(&self) -> String {
+        save::save_game(&self.game.borrow())
+    }
+
+    // Restores a snapshot and waits paused so the player can get ready
+    pub fn load_state(&self, json: String) -> Result<(), JsValue> {
+        let mut game = save::load_game(&json).map_err(|e| JsValue::from_str(&e))?;
+        // Assists follow the player's settings, not the ones saved with the run
+        game.set_assists(self.settings.borrow().assists);
+        let scene = if game.player.is_dead {
+            Scene::GameOver { elapsed: 0.0 }
+        } else {
+            Scene::Paused { selected: scene::PAUSE_RESUME }
+        };
+        *self.game.borrow_mut() = game;
+        *self.scene.borrow_mut() = scene;
+        Ok(())
+    }
+
+    // True while a run is underway and worth suspending
+    pub fn has_active_run(&self) -> bool {
+        matches!(
+            *self.scene.borrow(),
+            Scene::Playing
+                | Scene::Paused { .. }
+                | Scene::Options { from_pause: true, .. }
+                | Scene::Controls { from_pause: true, .. }
+        )
+    }
+
+    // Calls back with every game event as an object tagged by `type`, e.g.
+    // { type: "EnemyKilled", kind: "Goblin", x, y, airborne }
+    pub fn on_event(&self, callback: js_sys::Function) {
+        let forward = move |event: &GameEvent| {
+            let Ok(value) = js_sys::JSON::parse(&event.to_json()) else {
+                return;
+            };
+            if let Err(e) = callback.call1(&JsValue::NULL, &value) {
+                console_log!("Event callback failed: {:?}", e);
+            }
+        };
+        *self.event_callback.borrow_mut() = Some(Rc::new(forward));
+    }
+
+    // In hearts
+    pub fn health(&self) -> f64 {
+        self.game.borrow().player.health / 4.0
+    }
+
+    pub fn max_health(&self) -> f64 {
+        self.game.borrow().player.max_health / 4.0
+    }
+
+    // In meters
+    pub fn distance(&self) -> u32 {
+        (self.game.borrow().distance_traveled / 10.0) as u32
+    }
+
+    // A number rather than a BigInt, to keep the page simple
+    pub fn score(&self) -> f64 {
+        self.game.borrow().score.score() as f64
+    }
+
+    // The current scene, e.g. "title", "playing" or "game_over"
+    pub fn state(&self) -> String {
+        self.scene.borrow().name().to_string()
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.game.borrow().seed
+    }
+
+    // Stats for the current (or just finished) run as a plain JS object
+    pub fn get_run_summary(&self) -> JsValue {
+        let summary = self.game.borrow().score.summary();
+        serde_json::to_string(&summary)
+            .ok()
+            .and_then(|json| js_sys::JSON::parse(&json).ok())
+            .unwrap_or(JsValue::NULL)
+    }
+
+    // Lets the page pause when the tab loses focus
+    pub fn pause(&self) {
+        let mut scene = self.scene.borrow_mut();
+        if *scene == Scene::Playing {
+            *scene = Scene::Paused { selected: scene::PAUSE_RESUME };
+        }
+    }
+
+    fn poll_gamepads(&self) {
+        let mut merged = GamepadInput::default();
+        for snapshot in gamepad::poll_gamepads() {
+            merged.merge(&self.gamepad_mapping.map(&snapshot));
+        }
+        self.input.borrow_mut().set_gamepad(merged);
+    }
+
+    // Returns true if the key is bound so the page can suppress its default action
+    pub fn key_down(&self, key: String) -> bool {
+        self.audio.borrow_mut().unlock();
+        self.input.borrow_mut().key_down(&key)
+    }
+
+    pub fn key_up(&self, key: String) -> bool {
+        self.input.borrow_mut().key_up(&key)
+    }
+
+    // Touch coordinates are in canvas pixels. The start/move calls return
+    // true when the touch is over a virtual button.
+    pub fn touch_start(&self, id: i32, x: f64, y: f64) -> bool {
+        self.audio.borrow_mut().unlock();
+        let hit = self.touch.borrow_mut().touch_start(id, x, y);
+        self.sync_touch();
+        hit
+    }
+
+    pub fn touch_move(&self, id: i32, x: f64, y: f64) -> bool {
+        let hit = self.touch.borrow_mut().touch_move(id, x, y);
+        self.sync_touch();
+        hit
+    }
+
+    pub fn touch_end(&self, id: i32) {
+        self.touch.borrow_mut().touch_end(id);
+        self.sync_touch();
+    }
+
+    // Hands the tick's events to the systems outside the game
+    fn drain_events(&self, events: Vec<GameEvent>, summary: &RunSummary, assisted: bool) {
+        // Runs with assists earn nothing towards achievements, just as
+        // they are marked on the high score boards
+        if !assisted {
+            let mut achievements = self.achievements.borrow_mut();
+            if achievements.record(&events, summary) {
+                achievements.save(self.storage.borrow_mut().as_mut());
+            }
+        }
+
+        let volume = self.settings.borrow().sfx_volume as f64 / VOLUME_STEPS as f64;
+        let callback = self.event_callback.borrow().clone();
+        for event in events {
+            if let Some(sound) = Sfx::for_event(&event) {
+                self.audio.borrow().play(sound, volume);
+            }
+            if let Some(callback) = &callback {
+                callback(&event);
+            }
+        }
+    }
+
+    fn sync_touch(&self) {
+        let held = self.touch.borrow().held_actions();
+        self.input.borrow_mut().set_touch_held(held);
+    }
+
+    pub fn rebind_key(&self, action: String, key: String) -> Result<(), JsValue> {
+        let action = Action::from_name(&action)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown action: {}", action)))?;
+        self.settings.borrow_mut().key_map.rebind(action, &key);
+        self.apply_key_map();
+        Ok(())
+    }
+
+    pub fn save_key_map(&self) -> String {
+        self.settings.borrow().key_map.to_json()
+    }
+
+    pub fn load_key_map(&self, json: String) -> Result<(), JsValue> {
+        let key_map = KeyMap::from_json(&json).map_err(|e| JsValue::from_str(&e))?;
+        self.settings.borrow_mut().key_map = key_map;
+        self.apply_key_map();
+        Ok(())
+    }
+
+    // All user settings, including key bindings, as JSON
+    pub fn get_settings(&self) -> String {
+        self.settings.borrow().to_json()
+    }
+
+    // Fields left out of the JSON keep their defaults
+    pub fn set_settings(&self, json: String) -> Result<(), JsValue> {
+        let settings = Settings::from_json(&json).map_err(|e| JsValue::from_str(&e))?;
+        self.game.borrow_mut().set_assists(settings.assists);
+        *self.settings.borrow_mut() = settings;
+        self.apply_key_map();
+        Ok(())
+    }
+
+    // Canvas size multiplier the page should apply
+    pub fn pixel_scale(&self) -> u32 {
+        self.settings.borrow().pixel_scale
+    }
+
+    fn change_setting(&self, item: OptionItem, step: i32) {
+        let mut settings = self.settings.borrow_mut();
+        if item.adjust(&mut settings, step) {
+            settings.save(self.storage.borrow_mut().as_mut());
+            self.game.borrow_mut().set_assists(settings.assists);
+        }
+    }
+
+    // Hands the bindings in the settings to the input and saves them
+    fn apply_key_map(&self) {
+        let settings = self.settings.borrow();
+        self.input.borrow_mut().set_key_map(settings.key_map.clone());
+        settings.save(self.storage.borrow_mut().as_mut());
     }
 }
 
 #[wasm_bindgen(start)]
 pub fn main() {
     console_log!("Medieval Platformer WASM Module Loaded!");
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::ACTION_COUNT;
+
+    fn knight_at(x: f64, facing_right: bool) -> Player {
+        let mut player = Player::new();
+        player.x = x;
+        player.y = 300.0;
+        player.facing_right = facing_right;
+        player
+    }
+
+    // One physics frame: jump input followed by a simulation step
+    fn step(player: &mut Player, jump_held: bool, platforms: &[Platform]) {
+        let pressed = jump_held && !player.jump_held;
+        let released = !jump_held && player.jump_held;
+        player.jump_input(pressed, released, false);
+        player.update(1.0, platforms);
+    }
+
+    fn standing_on_ledge() -> (Player, Vec<Platform>) {
+        let platforms = vec![Platform::new(0.0, 200.0, 200.0, 20.0)];
+        let mut player = knight_at(100.0, true);
+        player.y = 200.0 - player.height;
+        step(&mut player, false, &platforms);
+        assert!(player.on_ground);
+        (player, platforms)
+    }
+
+    // Frames until a falling knight lands, without any input
+    fn frames_to_land(player: &Player, platforms: &[Platform]) -> u32 {
+        let mut probe = player.clone();
+        let mut frames = 0;
+        loop {
+            step(&mut probe, false, platforms);
+            if probe.on_ground {
+                return frames;
+            }
+            frames += 1;
+        }
+    }
+
+    fn peak_height(mut player: Player, platforms: &[Platform], hold_frames: u32) -> f64 {
+        let start_y = player.y;
+        let mut peak = player.y;
+        for frame in 0..200 {
+            step(&mut player, frame < hold_frames, platforms);
+            peak = peak.min(player.y);
+            if player.on_ground && frame > 0 {
+                break;
+            }
+        }
+        start_y - peak
+    }
+
+    #[test]
+    fn coyote_time_allows_jump_for_configured_frames() {
+        let coyote = MovementConfig::default().coyote_frames;
+
+        for airborne in [1, coyote, coyote + 1] {
+            let (mut player, _) = standing_on_ledge();
+            // The ledge disappears from under the knight
+            for _ in 0..airborne {
+                step(&mut player, false, &[]);
+            }
+            assert_eq!(player.air_frames, airborne);
+
+            player.jump_input(true, false, false);
+            let jumped = player.vel_y == -player.jump_power;
+            assert_eq!(jumped, airborne <= coyote, "airborne for {} frames", airborne);
+        }
+    }
+
+    #[test]
+    fn coyote_time_does_not_grant_a_second_jump() {
+        let (mut player, platforms) = standing_on_ledge();
+        step(&mut player, true, &platforms);
+        step(&mut player, false, &platforms);
+        player.jump_input(true, false, false);
+        assert!(player.vel_y > -player.jump_power);
+    }
+
+    #[test]
+    fn jump_buffer_fires_on_landing_within_window() {
+        let buffer = MovementConfig::default().jump_buffer_frames;
+        let platforms = vec![Platform::new(0.0, 400.0, 200.0, 20.0)];
+
+        for early in [0, buffer - 1, buffer] {
+            let mut player = knight_at(100.0, true);
+            player.y = 0.0;
+            let landing = frames_to_land(&player, &platforms);
+            for _ in 0..landing - early {
+                step(&mut player, false, &platforms);
+            }
+            assert!(player.air_frames > player.movement.coyote_frames);
+
+            // Press jump `early` frames before the landing frame
+            step(&mut player, true, &platforms);
+            for _ in 0..early {
+                step(&mut player, true, &platforms);
+            }
+            let jumped = player.vel_y < 0.0;
+            assert_eq!(jumped, early < buffer, "pressed {} frames early", early);
+        }
+    }
+
+    #[test]
+    fn releasing_jump_early_cuts_height() {
+        let (player, platforms) = standing_on_ledge();
+        let full = peak_height(player.clone(), &platforms, 200);
+        let tapped = peak_height(player.clone(), &platforms, 3);
+        assert!(tapped < full * 0.6, "tapped {} vs full {}", tapped, full);
+
+        let mut cut = player;
+        step(&mut cut, true, &platforms);
+        let rising = cut.vel_y;
+        cut.jump_input(false, true, false);
+        assert_eq!(cut.vel_y, rising * cut.movement.jump_cut_multiplier);
+    }
+
+    #[test]
+    fn apex_gravity_extends_hang_time() {
+        let (player, platforms) = standing_on_ledge();
+        let mut floaty = player.clone();
+        let mut plain = player;
+        plain.movement.apex_gravity_multiplier = 1.0;
+
+        let airtime = |mut knight: Player| {
+            step(&mut knight, true, &platforms);
+            let mut frames = 1;
+            while !knight.on_ground {
+                step(&mut knight, true, &platforms);
+                frames += 1;
+            }
+            frames
+        };
+        assert!(airtime(floaty.clone()) > airtime(plain));
+
+        // Gravity is only reduced while jump is held near the apex
+        floaty.vel_y = 0.5;
+        floaty.on_ground = false;
+        floaty.jump_held = false;
+        floaty.update(1.0, &[]);
+        assert_eq!(floaty.vel_y, 1.0);
+    }
+
+    fn with_abilities(mut player: Player, abilities: &[Ability]) -> Player {
+        for ability in abilities {
+            player.abilities.unlock(*ability);
+        }
+        player
+    }
+
+    // A falling knight pressed against the left face of a tall wall
+    fn against_wall(abilities: &[Ability]) -> (Player, Vec<Platform>) {
+        let walls = vec![Platform::new(200.0, 0.0, 40.0, 400.0)];
+        let mut player = with_abilities(knight_at(0.0, true), abilities);
+        player.x = 200.0 - player.width;
+        player.y = 100.0;
+        for _ in 0..20 {
+            player.move_right(1.0);
+            player.update(1.0, &walls);
+        }
+        (player, walls)
+    }
+
+    #[test]
+    fn double_jump_fires_once_per_airtime() {
+        let (player, platforms) = standing_on_ledge();
+        let mut player = with_abilities(player, &[Ability::DoubleJump]);
+        // Air jumps are handed out on landing
+        step(&mut player, false, &platforms);
+        step(&mut player, true, &platforms);
+        for _ in 0..5 {
+            step(&mut player, false, &platforms);
+        }
+
+        step(&mut player, true, &platforms);
+        assert_eq!(player.air_jumps_left, 0);
+        assert!(player.vel_y < -player.jump_power * 0.8);
+
+        // A third press only buffers a jump for the landing
+        step(&mut player, false, &platforms);
+        let rising = player.vel_y;
+        player.jump_input(true, false, false);
+        assert_eq!(player.vel_y, rising);
+        assert!(player.jump_buffer > 0);
+    }
+
+    #[test]
+    fn wall_slide_caps_falling_speed_and_wall_jump_kicks_away() {
+        let (mut player, walls) = against_wall(&[Ability::WallSlide, Ability::WallJump]);
+        assert_eq!(player.wall_contact, Some(WallSide::Right));
+        assert!(player.is_wall_sliding);
+        assert_eq!(player.vel_y, player.movement.wall_slide_speed);
+
+        player.jump_input(true, false, false);
+        assert!(player.vel_x < 0.0);
+        assert!(!player.facing_right);
+        assert_eq!(player.vel_y, -player.jump_power * 0.9);
+
+        // Steering back into the wall is ignored for a moment
+        player.update(1.0, &walls);
+        player.move_right(1.0);
+        assert!(player.vel_x < 0.0);
+    }
+
+    #[test]
+    fn air_dash_is_spent_until_landing_and_respects_cooldown() {
+        let mut player = with_abilities(knight_at(100.0, true), &[Ability::Dash]);
+        player.dash();
+        assert_eq!(player.vel_x, player.movement.dash_speed);
+        assert_eq!(player.vel_y, 0.0);
+
+        player.update(1.0, &[]);
+        let cooldown = player.dash_cooldown;
+        player.dash();
+        assert_eq!(player.dash_cooldown, cooldown);
+
+        // Off cooldown but still airborne: the air dash is used up
+        player.dash_cooldown = 0.0;
+        player.dash();
+        assert_eq!(player.dash_cooldown, 0.0);
+
+        while !player.on_ground {
+            player.update(1.0, &[]);
+        }
+        player.dash();
+        assert_eq!(player.dash_cooldown, player.movement.dash_cooldown);
+    }
+
+    #[test]
+    fn crouching_drops_through_one_way_platforms_only() {
+        for (height, drops) in [(ONE_WAY_MAX_HEIGHT, true), (50.0, false)] {
+            let platforms = vec![Platform::new(0.0, 200.0, 200.0, height)];
+            let mut player = with_abilities(knight_at(100.0, true), &[Ability::Crouch]);
+            player.y = 200.0 - player.height;
+            step(&mut player, false, &platforms);
+            assert_eq!(player.on_one_way, drops);
+
+            player.set_crouching(true);
+            assert_eq!(player.height, CROUCH_HEIGHT);
+            player.jump_input(true, false, true);
+            for _ in 0..20 {
+                player.update(1.0, &platforms);
+            }
+            assert_eq!(player.y > 200.0, drops, "platform {} high", height);
+        }
+    }
+
+    #[test]
+    fn locked_abilities_do_nothing() {
+        let (mut player, walls) = against_wall(&[]);
+        assert!(!player.is_wall_sliding);
+        assert!(player.vel_y > player.movement.wall_slide_speed);
+
+        // No wall jump, air jump or dash to fall back on
+        let falling = player.vel_y;
+        player.jump_input(true, false, false);
+        player.dash();
+        assert_eq!(player.vel_y, falling);
+        assert_eq!(player.dash_timer, 0.0);
+        player.update(1.0, &walls);
+
+        // Down + jump on a one-way platform is just a jump
+        let platforms = vec![Platform::new(0.0, 200.0, 200.0, ONE_WAY_MAX_HEIGHT)];
+        let mut player = knight_at(100.0, true);
+        player.y = 200.0 - player.height;
+        step(&mut player, false, &platforms);
+        player.set_crouching(true);
+        assert_eq!(player.height, STAND_HEIGHT);
+        player.jump_input(true, false, true);
+        assert_eq!(player.vel_y, -player.jump_power);
+    }
+
+    #[test]
+    fn abilities_unlock_at_their_distances() {
+        let mut game = Game::new(7, RunMode::Standard, &Upgrades::default(), Difficulty::Knight);
+        for (meters, ability) in ABILITY_UNLOCKS {
+            // High above the platforms, so nothing pushes the knight back
+            game.player.y = 0.0;
+            game.player.x = meters * 10.0 - 20.0;
+            game.update(1.0, &KeyState::default());
+            assert!(!game.player.abilities.has(ability), "{:?} before {}m", ability, meters);
+
+            game.player.x = meters * 10.0 + 5.0;
+            game.update(1.0, &KeyState::default());
+            assert!(game.player.abilities.has(ability), "{:?} at {}m", ability, meters);
+            assert_eq!(game.toast.clone().map(|(message, _)| message), Some(format!("{} UNLOCKED!", ability.name())));
+        }
+    }
+
+    #[test]
+    fn frontal_arc_follows_facing_direction() {
+        let right = knight_at(100.0, true);
+        let left = knight_at(100.0, false);
+        let (cx, cy) = (112.0, 316.0);
+
+        assert!(right.is_frontal(cx + 30.0, cy));
+        assert!(!right.is_frontal(cx - 30.0, cy));
+        assert!(left.is_frontal(cx - 30.0, cy));
+        assert!(!left.is_frontal(cx + 30.0, cy));
+    }
+
+    #[test]
+    fn frontal_arc_excludes_overhead_and_below() {
+        let player = knight_at(100.0, true);
+        let (cx, cy) = (112.0, 316.0);
+
+        // 45 degrees up and down are inside the 70 degree half-arc
+        assert!(player.is_frontal(cx + 20.0, cy - 20.0));
+        assert!(player.is_frontal(cx + 20.0, cy + 20.0));
+        // Straight above or below is never covered by the shield
+        assert!(!player.is_frontal(cx, cy - 40.0));
+        assert!(!player.is_frontal(cx + 5.0, cy + 40.0));
+    }
+
+    #[test]
+    fn parry_window_then_block_costs_stamina() {
+        let mut player = knight_at(100.0, true);
+        player.set_blocking(true);
+        assert_eq!(player.receive_hit(150.0, 316.0), HitOutcome::Parried);
+        assert_eq!(player.stamina, 100.0);
+
+        // Let the parry window lapse
+        player.update(20.0, &[]);
+        assert_eq!(player.receive_hit(150.0, 316.0), HitOutcome::Blocked);
+        assert_eq!(player.stamina, 100.0 - BLOCK_STAMINA_COST);
+        assert_eq!(player.health, 28.0);
+    }
+
+    #[test]
+    fn quick_raises_dont_chain_parries() {
+        let mut player = knight_at(100.0, true);
+        player.set_blocking(true);
+        assert_eq!(player.receive_hit(150.0, 316.0), HitOutcome::Parried);
+
+        // Lower and raise again a few frames later
+        player.set_blocking(false);
+        player.update(5.0, &[]);
+        player.set_blocking(true);
+        assert_eq!(player.receive_hit(150.0, 316.0), HitOutcome::Blocked);
+
+        // Once the cooldown is over the next raise parries again
+        player.set_blocking(false);
+        player.update(PARRY_COOLDOWN / 0.016, &[]);
+        player.set_blocking(true);
+        assert_eq!(player.receive_hit(150.0, 316.0), HitOutcome::Parried);
+    }
+
+    #[test]
+    fn hits_from_behind_or_without_stamina_land() {
+        let mut player = knight_at(100.0, true);
+        player.set_blocking(true);
+        assert_eq!(player.receive_hit(50.0, 316.0), HitOutcome::Damaged);
+        assert_eq!(player.health, 27.0);
+
+        let mut tired = knight_at(100.0, true);
+        tired.set_blocking(true);
+        tired.parry_timer = 0.0;
+        tired.stamina = BLOCK_STAMINA_COST - 1.0;
+        assert_eq!(tired.receive_hit(150.0, 316.0), HitOutcome::Damaged);
+        assert!(!tired.is_blocking);
+    }
+
+    #[test]
+    fn shield_power_up_absorbs_one_hit_from_behind() {
+        let mut player = knight_at(100.0, true);
+        player.effects.grant(EffectKind::Shield);
+        assert_eq!(player.receive_hit(50.0, 316.0), HitOutcome::Blocked);
+        assert!(!player.effects.has(EffectKind::Shield));
+        assert_eq!(player.receive_hit(50.0, 316.0), HitOutcome::Damaged);
+    }
+
+    #[test]
+    fn poison_drains_but_never_kills() {
+        let mut player = knight_at(100.0, true);
+        player.health = 3.0;
+        player.effects.grant(EffectKind::Poison);
+        // 1.5 s per quarter at 0.016 s per frame unit
+        player.update(POISON_INTERVAL / 0.016, &[]);
+        assert_eq!(player.health, 2.0);
+        for _ in 0..3 {
+            player.update(POISON_INTERVAL / 0.016, &[]);
+        }
+        assert_eq!(player.health, 1.0);
+        assert!(!player.is_dead);
+    }
+
+    #[test]
+    fn heart_containers_raise_the_cap_up_to_the_limit() {
+        let mut player = knight_at(100.0, true);
+        player.health = 20.0;
+        player.raise_max_health(4.0);
+        assert_eq!((player.health, player.max_health), (24.0, 32.0));
+        player.heal(100.0);
+        assert_eq!(player.health, 32.0);
+
+        player.raise_max_health(MAX_HEALTH_CAP);
+        assert_eq!(player.max_health, MAX_HEALTH_CAP);
+        assert_eq!(player.health, MAX_HEALTH_CAP);
+    }
+
+    // Stand the knight on the first banner and let a goblin land a fatal hit
+    fn die_after_checkpoint(mode: RunMode) -> Game {
+        let mut game = Game::new(7, mode, &Upgrades::default(), Difficulty::Knight);
+        while game.checkpoints.is_empty() {
+            game.camera_x += 800.0;
+            game.generate_platforms_ahead();
+        }
+        let banner = game.checkpoints[0].clone();
+        game.player.x = banner.x - 4.0;
+        game.player.y = banner.y - game.player.height;
+        game.player.health = 10.0;
+        game.update(1.0, &KeyState::default());
+        assert_eq!(game.checkpoints_reached, 1);
+
+        game.player.health = 1.0;
+        let (x, y) = (game.player.x, game.player.y);
+        game.enemies.push(Enemy::new(x, y, 0.0));
+        game.update(1.0, &KeyState::default());
+        game
+    }
+
+    #[test]
+    fn lives_mode_respawns_at_last_checkpoint() {
+        let game = die_after_checkpoint(RunMode::Lives);
+        let banner = &game.checkpoints[0];
+        assert!(!game.player.is_dead);
+        assert_eq!(game.lives, LIVES_MODE_LIVES - 1);
+        assert_eq!(game.player.x, banner.x);
+        // The half-health minimum beats the 2.5 hearts held at the checkpoint
+        assert_eq!(game.player.health, 16.0);
+        assert!(game.player.invincible);
+        assert!(game.events.contains(&GameEvent::PlayerRespawned { lives: LIVES_MODE_LIVES - 1 }));
+        assert!(!game.events.contains(&GameEvent::PlayerDied));
+    }
+
+    #[test]
+    fn a_death_before_the_first_checkpoint_respawns_on_the_starting_ground() {
+        let mut game = Game::new(7, RunMode::Lives, &Upgrades::default(), Difficulty::Knight);
+        // Carry the knight out to just short of the first banner
+        while game.player.x < 1400.0 {
+            game.enemies.clear();
+            game.player.x += 8.0;
+            game.update(1.0, &KeyState::default());
+        }
+        assert!(game.respawn.is_none());
+
+        game.player.health = 1.0;
+        let (x, y) = (game.player.x, game.player.y);
+        game.enemies = vec![Enemy::new(x, y, 0.0)];
+        game.update(1.0, &KeyState::default());
+        assert_eq!(game.lives, LIVES_MODE_LIVES - 1);
+        assert_eq!(game.player.x, RESPAWN_START.0);
+
+        // The knight lands on the starting ground rather than below it
+        game.enemies.clear();
+        for _ in 0..60 {
+            game.update(1.0, &KeyState::default());
+        }
+        assert!(game.player.on_ground && !game.player.is_dead);
+        assert_eq!(game.player.y + game.player.height, RESPAWN_START.1);
+        assert_eq!(game.lives, LIVES_MODE_LIVES - 1);
+    }
+
+    #[test]
+    fn standard_mode_death_is_final() {
+        let game = die_after_checkpoint(RunMode::Standard);
+        assert!(game.player.is_dead);
+        assert_eq!(game.lives, 0);
+        assert!(game.events.contains(&GameEvent::PlayerDied));
+    }
+
+    #[test]
+    fn difficulty_sets_hit_damage_and_assists_are_remembered() {
+        let mut game = Game::new(7, RunMode::Standard, &Upgrades::default(), Difficulty::Paladin);
+        assert_eq!(game.player.hit_damage, 2.0);
+        assert!(game.player.iframe_duration < 1.0);
+
+        game.set_assists(Assists { infinite_health: true, auto_attack: true, ..Assists::default() });
+        game.set_assists(Assists::default());
+        assert_eq!(game.assists_used.names(), vec!["infinite health", "auto-attack"]);
+    }
+
+    #[test]
+    fn music_follows_distance_and_speeds_up_for_a_brute_on_screen() {
+        let mut game = Game::new(7, RunMode::Standard, &Upgrades::default(), Difficulty::Knight);
+        let bands = [(0.0, Track::Meadow), (299.0, Track::Meadow), (300.0, Track::Forest), (800.0, Track::Castle)];
+        for (meters, track) in bands {
+            game.distance_traveled = meters * 10.0;
+            assert_eq!(game.tune(), (track, false), "at {}m", meters);
+        }
+
+        game.distance_traveled = 0.0;
+        game.enemies.push(Enemy::spawn(EnemyKind::Brute, game.camera_x + 400.0, 300.0, 80.0));
+        assert_eq!(game.tune(), (Track::Meadow, true));
+
+        // Off either edge of the screen, or dead, it no longer counts
+        game.enemies.last_mut().unwrap().x = game.camera_x + ui::SCREEN.w + 10.0;
+        assert_eq!(game.tune(), (Track::Meadow, false));
+        game.enemies.last_mut().unwrap().x = game.camera_x - 200.0;
+        assert_eq!(game.tune(), (Track::Meadow, false));
+        let brute = game.enemies.last_mut().unwrap();
+        brute.x = game.camera_x + 400.0;
+        brute.is_alive = false;
+        assert_eq!(game.tune(), (Track::Meadow, false));
+    }
+
+    #[test]
+    fn daily_modifiers_reshape_the_run() {
+        let all = Modifiers { low_gravity: true, double_enemies: true, one_hit_death: true, no_hearts: true };
+        let upgrades = Upgrades::default();
+        let mut plain = Game::new(7, RunMode::Daily, &upgrades, Difficulty::Knight);
+        let mut daily = Game::with_modifiers(7, RunMode::Daily, &upgrades, Difficulty::Knight, all);
+        assert!(daily.player.movement.gravity < plain.player.movement.gravity);
+
+        // Same layout further out, but busier and without hearts
+        for game in [&mut plain, &mut daily] {
+            game.distance_traveled = 3000.0;
+            game.camera_x = 20000.0;
+            game.generate_platforms_ahead();
+        }
+        assert!(daily.enemies.len() > plain.enemies.len());
+        assert!(plain.pickups.iter().any(|pickup| pickup.kind == PickupKind::Heart));
+        assert!(!daily.pickups.iter().any(|pickup| pickup.kind == PickupKind::Heart));
+
+        // A single goblin hit at full health ends it
+        let mut game = Game::with_modifiers(7, RunMode::Daily, &upgrades, Difficulty::Knight, all);
+        game.player.x = 100.0;
+        game.player.y = 450.0 - game.player.height;
+        game.enemies.push(Enemy::new(game.player.x + 8.0, game.player.y, 0.0));
+        game.update(1.0, &KeyState::default());
+        assert!(game.player.is_dead);
+        assert!(matches!(game.events[..], [GameEvent::PlayerDamaged { health: 0.0, .. }, ..]));
+    }
+
+    #[test]
+    fn assists_keep_the_knight_alive_and_swing_for_them() {
+        let mut game = Game::new(7, RunMode::Standard, &Upgrades::default(), Difficulty::Knight);
+        game.set_assists(Assists { infinite_health: true, ..Assists::default() });
+        game.player.x = 100.0;
+        game.player.y = 450.0 - game.player.height;
+        game.player.health = 1.0;
+        // Overlapping the knight, just in front of them
+        let (x, y) = (game.player.x + 8.0, game.player.y);
+        game.enemies.push(Enemy::new(x, y, 0.0));
+
+        game.update(1.0, &KeyState::default());
+        assert_eq!(game.score.summary().damage_taken, 0.25);
+        assert!(!game.player.is_dead);
+        assert_eq!(game.player.health, game.player.max_health - 1.0);
+        assert!(!game.player.is_attacking);
+        assert!(matches!(game.events[..], [GameEvent::PlayerDamaged { quarters: 1.0, .. }]));
+
+        game.events.clear();
+        game.set_assists(Assists { auto_attack: true, ..Assists::default() });
+        game.update(1.0, &KeyState::default());
+        assert!(game.player.is_attacking);
+        assert_eq!(game.events.first(), Some(&GameEvent::SwordSwung));
+    }
+
+    fn engine() -> GameEngine {
+        GameEngine::with_storage(Box::new(MemoryStorage::default()))
+    }
+
+    // One tick's input with the action just pressed
+    fn pressing(action: Action) -> KeyState {
+        let mut held = [false; ACTION_COUNT];
+        held[action as usize] = true;
+        let mut input = Input::default();
+        input.set_touch_held(held);
+        input.tick().clone()
+    }
+
+    fn scene_of(engine: &GameEngine) -> Scene {
+        *engine.scene.borrow()
+    }
+
+    #[test]
+    fn title_starts_the_waiting_run_and_pause_freezes_it() {
+        let engine = engine();
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Playing);
+        for _ in 0..10 {
+            engine.advance(1.0, &KeyState::default());
+        }
+
+        engine.advance(1.0, &pressing(Action::Pause));
+        assert_eq!(scene_of(&engine), Scene::Paused { selected: scene::PAUSE_RESUME });
+        let frozen = engine.save_state();
+        for _ in 0..30 {
+            engine.advance(1.0, &KeyState::default());
+        }
+        assert_eq!(engine.save_state(), frozen);
+
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Playing);
+        engine.advance(1.0, &KeyState::default());
+        assert_ne!(engine.save_state(), frozen);
+    }
+
+    #[test]
+    fn game_over_leads_to_results_and_retry_keeps_the_seed() {
+        let engine = engine();
+        engine.advance(1.0, &pressing(Action::Confirm));
+        let seed = engine.game.borrow().seed;
+        engine.game.borrow_mut().player.is_dead = true;
+        engine.advance(1.0, &KeyState::default());
+        assert!(matches!(scene_of(&engine), Scene::GameOver { .. }));
+
+        // The banner stays up for its delay, then the run is recorded
+        engine.advance(GAME_OVER_DELAY / 0.016, &KeyState::default());
+        assert_eq!(scene_of(&engine), Scene::Results { rank: Some(1), selected: scene::RESULTS_NEW_RUN });
+        assert_eq!(engine.high_scores.borrow().board(STANDARD_MODE, None).len(), 1);
+
+        engine.advance(1.0, &pressing(Action::Down));
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Playing);
+        let game = engine.game.borrow();
+        assert_eq!(game.seed, seed);
+        assert!(!game.player.is_dead);
+        assert_eq!(game.distance_traveled, 0.0);
+    }
+
+    #[test]
+    fn loaded_runs_take_the_current_assists() {
+        let engine = engine();
+        let assists = Assists { infinite_health: true, ..Assists::default() };
+        engine.settings.borrow_mut().assists = assists;
+        engine.game.borrow_mut().set_assists(assists);
+        let saved = engine.save_state();
+
+        // Switched off before the run is resumed
+        engine.settings.borrow_mut().assists = Assists::default();
+        engine.load_state(saved).unwrap();
+        let game = engine.game.borrow();
+        assert_eq!(game.assists, Assists::default());
+        assert_eq!(game.assists_used.names(), vec!["infinite health"]);
+    }
+
+    #[test]
+    fn failed_purchases_flash_their_reason() {
+        let engine = engine();
+        *engine.scene.borrow_mut() = Scene::Shop { selected: 0, flash: 0.0 };
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Shop { selected: 0, flash: scene::SHOP_FLASH_TIME });
+        assert!(engine.progress.borrow().price(0).unwrap_err().contains("more coins"));
+
+        // The message fades, and a purchase that goes through clears it
+        engine.advance(scene::SHOP_FLASH_TIME / 0.016, &KeyState::default());
+        assert_eq!(scene_of(&engine), Scene::Shop { selected: 0, flash: 0.0 });
+        engine.advance(1.0, &pressing(Action::Confirm));
+        engine.progress.borrow_mut().coins = 1000;
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Shop { selected: 0, flash: 0.0 });
+        assert_eq!(engine.progress.borrow().upgrades.level(&shop::UPGRADES[0]), 1);
+    }
+
+    #[test]
+    fn level_up_choices_ignore_a_mashed_attack() {
+        let engine = engine();
+        engine.game.borrow_mut().player.experience.pending = 1;
+        *engine.scene.borrow_mut() = Scene::LevelUp { selected: 0, elapsed: 0.0 };
+        engine.advance(1.0, &pressing(Action::Attack));
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert!(matches!(scene_of(&engine), Scene::LevelUp { selected: 0, .. }));
+        assert_eq!(engine.game.borrow().player.experience.pending, 1);
+
+        engine.advance(scene::LEVEL_UP_INPUT_DELAY / 0.016, &KeyState::default());
+        engine.advance(1.0, &pressing(Action::Confirm));
+        assert_eq!(scene_of(&engine), Scene::Playing);
+        assert_eq!(engine.game.borrow().player.experience.pending, 0);
+    }
+
+    // Events a test callback saw, with the scene name at the time
+    type SeenEvents = Rc<RefCell<Vec<(GameEvent, &'static str)>>>;
+
+    // A playing engine with a goblin overlapping the knight
+    fn engine_about_to_be_hit(pause_on_hit: bool) -> (GameEngine, SeenEvents) {
+        let engine = engine();
+        engine.advance(1.0, &pressing(Action::Confirm));
+        {
+            let mut game = engine.game.borrow_mut();
+            game.player.x = 100.0;
+            game.player.y = 450.0 - game.player.height;
+            let (x, y) = (game.player.x + 8.0, game.player.y);
+            game.enemies.push(Enemy::new(x, y, 0.0));
+        }
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let (log, shared) = (seen.clone(), engine.scene.clone());
+        let callback = move |event: &GameEvent| {
+            log.borrow_mut().push((*event, shared.borrow().name()));
+            // As a page calling pause() from its handler would
+            if pause_on_hit && matches!(event, GameEvent::PlayerDamaged { .. }) {
+                *shared.borrow_mut() = Scene::Paused { selected: scene::PAUSE_RESUME };
+            }
+        };
+        *engine.event_callback.borrow_mut() = Some(Rc::new(callback));
+        (engine, seen)
+    }
+
+    #[test]
+    fn event_callbacks_see_the_new_scene_and_keep_their_changes() {
+        let (engine, _) = engine_about_to_be_hit(true);
+        engine.advance(1.0, &KeyState::default());
+        assert_eq!(scene_of(&engine), Scene::Paused { selected: scene::PAUSE_RESUME });
+
+        let (engine, seen) = engine_about_to_be_hit(false);
+        engine.game.borrow_mut().player.health = 1.0;
+        engine.advance(1.0, &KeyState::default());
+        assert!(matches!(scene_of(&engine), Scene::GameOver { .. }));
+        assert!(seen.borrow().contains(&(GameEvent::PlayerDied, "game_over")));
+    }
+
+    #[test]
+    fn assisted_runs_earn_no_achievements() {
+        let engine = engine();
+        let run = RunSummary { distance: 150, ..RunSummary::default() };
+        let heart = GameEvent::PickupCollected { kind: PickupKind::Heart, x: 0.0, y: 0.0, at_full_health: true };
+        engine.drain_events(vec![heart], &run, true);
+        assert_eq!(engine.achievements.borrow().unlocked_count(), 0);
+
+        engine.drain_events(vec![heart], &run, false);
+        assert_eq!(engine.achievements.borrow().unlocked_count(), 2);
+    }
+
+    #[test]
+    fn daily_runs_ignore_the_players_difficulty_and_assists() {
+        let engine = engine();
+        engine.set_daily_date("2026-10-18".to_string()).unwrap();
+        {
+            let mut settings = engine.settings.borrow_mut();
+            settings.difficulty = Difficulty::Squire;
+            settings.assists = Assists { infinite_health: true, ..Assists::default() };
+        }
+        engine.replace_run(false, RunMode::Daily);
+        engine.change_setting(OptionItem::AutoAttack, 1);
+
+        let game = engine.game.borrow();
+        assert_eq!(game.difficulty, DAILY_DIFFICULTY);
+        assert_eq!(game.player.hit_damage, DAILY_DIFFICULTY.enemy_damage());
+        assert!(!game.assists.any());
+        assert!(!game.assists_used.any());
+        assert_eq!(game.seed, DailyChallenge::for_date("2026-10-18").unwrap().seed);
+    }
+}
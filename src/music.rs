@@ -0,0 +1,435 @@
+// A small tracker for the background music. Songs are written as text
+// patterns, one row per line and one 3-character cell per channel:
+//
+//     C-5 C-3 C-7     pulse lead, triangle bass, noise drums
+//     ... ... ...     ... keeps the previous note sounding
+//     === ... C-5     === silences the channel
+//
+// Noise cells use the note only to pick how fast the noise is clocked, so
+// higher notes sound like hats and lower ones like snares and kicks.
+
+// Rows are sixteenth notes
+pub const ROWS_PER_BEAT: f64 = 4.0;
+// Tempo multiplier while a brute is on screen
+pub const BOSS_TEMPO: f64 = 1.25;
+
+const CHANNELS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Cell {
+    Hold,
+    Off,
+    Note(f64),  // Hz
+}
+
+fn parse_cell(cell: &str) -> Result<Cell, String> {
+    match cell {
+        "..." => return Ok(Cell::Hold),
+        "===" => return Ok(Cell::Off),
+        _ => {}
+    }
+    let invalid = || format!("Invalid cell '{}'", cell);
+    let chars: Vec<char> = cell.chars().collect();
+    if chars.len() != 3 {
+        return Err(invalid());
+    }
+    let semitone = match chars[0] {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return Err(invalid()),
+    };
+    let sharp = match chars[1] {
+        '-' => 0,
+        '#' => 1,
+        _ => return Err(invalid()),
+    };
+    let octave = chars[2].to_digit(10).ok_or_else(invalid)? as i32;
+    let midi = (octave + 1) * 12 + semitone + sharp;
+    Ok(Cell::Note(440.0 * 2f64.powf((midi - 69) as f64 / 12.0)))
+}
+
+fn parse_pattern(text: &str) -> Result<Vec<[Cell; CHANNELS]>, String> {
+    let mut rows = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let cells = line
+            .split_whitespace()
+            .map(parse_cell)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        let row: [Cell; CHANNELS] = cells
+            .try_into()
+            .map_err(|_| format!("Line {}: expected {} cells", index + 1, CHANNELS))?;
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Song {
+    bpm: f64,
+    rows: Vec<[Cell; CHANNELS]>,
+}
+
+impl Song {
+    // The patterns play in the given order, then the song loops
+    pub fn parse(bpm: f64, patterns: &[&str], order: &[usize]) -> Result<Song, String> {
+        let patterns = patterns.iter().map(|text| parse_pattern(text)).collect::<Result<Vec<_>, _>>()?;
+        let mut rows = Vec::new();
+        for &index in order {
+            let pattern = patterns.get(index).ok_or_else(|| format!("No pattern {}", index))?;
+            rows.extend_from_slice(pattern);
+        }
+        if rows.is_empty() {
+            return Err("Song has no rows".to_string());
+        }
+        Ok(Song { bpm, rows })
+    }
+
+    // Length of one pass through the song
+    pub fn loop_samples(&self, sample_rate: u32, tempo: f64) -> usize {
+        let row_seconds = 60.0 / (self.bpm * tempo * ROWS_PER_BEAT);
+        (self.rows.len() as f64 * row_seconds * sample_rate as f64).round() as usize
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Channel {
+    freq: f64,
+    on: bool,
+    phase: f64,
+    age: f64,  // Seconds since the note started
+    lfsr: u16,  // Noise shift register
+}
+
+// Plays a song from the top, looping forever
+pub struct Sequencer {
+    song: Song,
+    tempo: f64,
+    row: usize,
+    position: usize,  // Samples into the current pass
+    channels: [Channel; CHANNELS],
+}
+
+impl Sequencer {
+    pub fn new(song: Song, tempo: f64) -> Self {
+        let mut sequencer = Sequencer {
+            song,
+            tempo,
+            row: 0,
+            position: 0,
+            channels: [Channel { lfsr: 1, ..Channel::default() }; CHANNELS],
+        };
+        sequencer.trigger_row();
+        sequencer
+    }
+
+    fn trigger_row(&mut self) {
+        for (channel, cell) in self.channels.iter_mut().zip(self.song.rows[self.row]) {
+            match cell {
+                Cell::Hold => {}
+                Cell::Off => channel.on = false,
+                Cell::Note(freq) => {
+                    channel.freq = freq;
+                    channel.on = true;
+                    channel.age = 0.0;
+                }
+            }
+        }
+    }
+
+    // Fills the buffer with the next stretch of the song. Playback carries
+    // on from where the last call stopped, so chunks join without gaps.
+    // Rows start on whole samples measured from the top of the pass, so a
+    // pass is exactly loop_samples long however it is split up.
+    pub fn render(&mut self, out: &mut [f32], sample_rate: u32) {
+        let step = 1.0 / sample_rate as f64;
+        let length = self.song.loop_samples(sample_rate, self.tempo);
+        let rows = self.song.rows.len();
+        for sample in out.iter_mut() {
+            let [pulse, triangle, noise] = &mut self.channels;
+            let mut mix = 0.0;
+            if pulse.on {
+                // A short pluck that settles to half volume
+                let level = (1.0 - pulse.age * 2.0).max(0.5);
+                mix += if pulse.phase < 0.25 { 0.25 } else { -0.25 } * level;
+            }
+            if triangle.on {
+                mix += (4.0 * (triangle.phase - 0.5).abs() - 1.0) * 0.3;
+            }
+            if noise.on {
+                let level = (1.0 - noise.age / 0.12).max(0.0);
+                mix += if noise.lfsr & 1 == 1 { 0.15 } else { -0.15 } * level;
+            }
+            *sample = mix as f32;
+
+            for channel in &mut self.channels {
+                channel.age += step;
+                channel.phase += channel.freq * step;
+                if channel.phase >= 1.0 {
+                    channel.phase -= channel.phase.floor();
+                    // 15-bit register like the NES noise channel
+                    let bit = (channel.lfsr ^ (channel.lfsr >> 1)) & 1;
+                    channel.lfsr = (channel.lfsr >> 1) | (bit << 14);
+                }
+            }
+
+            self.position = (self.position + 1) % length;
+            let row = self.position * rows / length;
+            if row != self.row {
+                self.row = row;
+                self.trigger_row();
+            }
+        }
+    }
+}
+
+// One pass through the song, ready to be looped by a buffer source
+pub fn render_loop(song: &Song, sample_rate: u32, tempo: f64) -> Vec<f32> {
+    let mut samples = vec![0.0; song.loop_samples(sample_rate, tempo)];
+    Sequencer::new(song.clone(), tempo).render(&mut samples, sample_rate);
+    samples
+}
+
+// Background tracks. The level has no set areas, so the run's music moves
+// through meadow, forest and castle tracks as the distance grows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Track {
+    Title,
+    Meadow,
+    Forest,
+    Castle,
+}
+
+impl Track {
+    pub const ALL: [Track; 4] = [Track::Title, Track::Meadow, Track::Forest, Track::Castle];
+
+    pub fn for_distance(meters: f64) -> Track {
+        if meters < 300.0 {
+            Track::Meadow
+        } else if meters < 800.0 {
+            Track::Forest
+        } else {
+            Track::Castle
+        }
+    }
+
+    pub fn song(self) -> Song {
+        let song = match self {
+            Track::Title => Song::parse(90.0, &[TITLE_A, TITLE_B], &[0, 1]),
+            Track::Meadow => Song::parse(120.0, &[MEADOW_A, MEADOW_B], &[0, 1, 0, 1]),
+            Track::Forest => Song::parse(132.0, &[FOREST_A, FOREST_B], &[0, 1, 0, 1]),
+            Track::Castle => Song::parse(144.0, &[CASTLE_A, CASTLE_B], &[0, 0, 1, 1]),
+        };
+        song.expect("built-in songs parse")
+    }
+}
+
+const TITLE_A: &str = "
+A-4 A-2 ...
+... ... ...
+C-5 ... ...
+... ... ...
+E-5 E-2 ...
+... ... ...
+D-5 ... ...
+C-5 ... ...
+B-4 G-2 ...
+... ... ...
+G-4 ... ...
+... ... ...
+A-4 A-2 ...
+... ... ...
+=== ... ...
+... ... ...
+";
+
+const TITLE_B: &str = "
+F-4 F-2 ...
+... ... ...
+A-4 ... ...
+C-5 ... ...
+E-5 C-3 ...
+... ... ...
+D-5 ... ...
+... ... ...
+C-5 G-2 ...
+B-4 ... ...
+A-4 ... ...
+G-4 ... ...
+A-4 A-2 ...
+... ... ...
+... ... ...
+=== === ...
+";
+
+const MEADOW_A: &str = "
+C-5 C-3 C-7
+... ... ...
+E-5 ... C-7
+... ... ...
+G-5 G-2 C-5
+... ... ...
+E-5 ... C-7
+... ... ...
+F-5 F-2 C-7
+... ... ...
+A-5 ... C-7
+... ... ...
+G-5 G-2 C-5
+... ... ...
+=== ... C-7
+... ... C-7
+";
+
+const MEADOW_B: &str = "
+E-5 A-2 C-7
+... ... ...
+D-5 ... C-7
+C-5 ... ...
+D-5 F-2 C-5
+... ... ...
+E-5 ... C-7
+... ... ...
+D-5 G-2 C-7
+... ... ...
+B-4 ... C-7
+... ... ...
+C-5 C-3 C-5
+... ... ...
+=== ... C-7
+... ... C-5
+";
+
+const FOREST_A: &str = "
+D-5 D-3 C-6
+... ... ...
+F-5 ... C-7
+A-5 ... ...
+G-5 A#2 C-5
+F-5 ... ...
+E-5 ... C-7
+... ... ...
+D-5 C-3 C-6
+... ... ...
+E-5 ... C-7
+F-5 ... ...
+E-5 A-2 C-5
+C#5 ... ...
+A-4 ... C-7
+=== ... C-7
+";
+
+const FOREST_B: &str = "
+A#4 A#2 C-6
+... ... ...
+D-5 ... C-7
+F-5 ... ...
+E-5 C-3 C-5
+... ... ...
+G-5 ... C-7
+... ... ...
+F-5 A-2 C-6
+E-5 ... ...
+D-5 ... C-7
+C#5 ... ...
+D-5 D-3 C-5
+... ... ...
+=== ... C-7
+... ... C-5
+";
+
+const CASTLE_A: &str = "
+E-5 E-2 C-5
+... E-3 C-7
+E-5 E-2 C-7
+G-5 E-3 C-7
+F#5 E-2 C-4
+... E-3 C-7
+E-5 E-2 C-7
+D-5 E-3 C-7
+E-5 C-3 C-5
+... C-2 C-7
+B-4 C-3 C-7
+... C-2 C-7
+D-5 D-3 C-4
+... D-2 C-7
+F#5 D-3 C-4
+... D-2 C-7
+";
+
+const CASTLE_B: &str = "
+G-5 G-2 C-5
+... G-3 C-7
+F#5 G-2 C-7
+E-5 G-3 C-7
+D-5 D-2 C-4
+... D-3 C-7
+B-4 D-2 C-7
+... D-3 C-7
+C-5 A-2 C-5
+D-5 A-3 C-7
+E-5 B-2 C-7
+F#5 B-3 C-7
+E-5 E-2 C-4
+... E-3 C-4
+=== E-2 C-4
+... ... C-4
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_parse_notes_and_reject_bad_rows() {
+        let song = Song::parse(120.0, &["A-4 ... ===\n\nC#5 A-2 ..."], &[0, 0]).unwrap();
+        assert_eq!(song.rows.len(), 4);
+        assert_eq!(song.rows[0], [Cell::Note(440.0), Cell::Hold, Cell::Off]);
+        let Cell::Note(c_sharp) = song.rows[1][0] else { panic!() };
+        assert!((c_sharp - 554.37).abs() < 0.01);
+
+        assert_eq!(Song::parse(120.0, &["A-4 ..."], &[0]).unwrap_err(), "Line 1: expected 3 cells");
+        assert_eq!(Song::parse(120.0, &["H-4 ... ..."], &[0]).unwrap_err(), "Line 1: Invalid cell 'H-4'");
+        assert!(Song::parse(120.0, &["A-4 ... ..."], &[1]).is_err());
+    }
+
+    #[test]
+    fn tracks_render_bounded_loops_that_speed_up_for_bosses() {
+        for track in Track::ALL {
+            let song = track.song();
+            let samples = render_loop(&song, 22050, 1.0);
+            assert_eq!(samples.len(), song.loop_samples(22050, 1.0));
+            assert!(samples.iter().all(|s| s.abs() <= 0.7), "{:?}", track);
+            assert!(samples.iter().any(|s| s.abs() > 0.2), "{:?} is silent", track);
+
+            let boss = render_loop(&song, 22050, BOSS_TEMPO);
+            let expected = samples.len() as f64 / BOSS_TEMPO;
+            assert!((boss.len() as f64 - expected).abs() <= 1.0, "{:?}", track);
+        }
+    }
+
+    #[test]
+    fn rendering_in_chunks_loops_seamlessly() {
+        let song = Track::Meadow.song();
+        let length = song.loop_samples(22050, 1.0);
+
+        // What the sequencer plays live, fed a block at a time
+        let mut sequencer = Sequencer::new(song.clone(), 1.0);
+        let mut live = vec![0.0; length];
+        for chunk in live.chunks_mut(1000) {
+            sequencer.render(chunk, 22050);
+        }
+        assert_eq!(live, render_loop(&song, 22050, 1.0));
+        // One pass later the song is back at the top, ready to go round again
+        assert_eq!((sequencer.row, sequencer.position), (0, 0));
+    }
+}
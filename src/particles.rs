@@ -0,0 +1,100 @@
+use crate::events::GameEvent;
+use web_sys::CanvasRenderingContext2d;
+
+const LIFETIME: f64 = 0.6;  // Seconds
+const GRAVITY: f64 = 0.25;
+const SIZE: f64 = 3.0;
+
+struct Particle {
+    x: f64,
+    y: f64,
+    vel_x: f64,
+    vel_y: f64,
+    life: f64,  // Seconds left
+    color: &'static str,
+}
+
+// Short-lived sparks thrown off by hits, kills and pickups. Purely visual,
+// so they aren't saved with the run.
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    seed: u32,
+}
+
+impl Particles {
+    pub fn handle(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::EnemyHit { x, y } => self.burst(x, y, 6, "#FFFFFF"),
+            GameEvent::EnemyKilled { x, y, .. } => self.burst(x, y, 14, "#7CB342"),
+            GameEvent::PlayerDamaged { x, y, .. } => self.burst(x, y, 8, "#E53935"),
+            GameEvent::PickupCollected { x, y, .. } => self.burst(x, y, 8, "#FFD700"),
+            GameEvent::BombExploded { x, y } => self.burst(x, y, 24, "#FF8C00"),
+            _ => {}
+        }
+    }
+
+    // Random value in -1.0..1.0
+    fn spread(&mut self) -> f64 {
+        self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
+        ((self.seed >> 16) & 0x7fff) as f64 / 16384.0 - 1.0
+    }
+
+    fn burst(&mut self, x: f64, y: f64, count: usize, color: &'static str) {
+        for _ in 0..count {
+            let particle = Particle {
+                x,
+                y,
+                vel_x: self.spread() * 3.0,
+                vel_y: self.spread() * 2.0 - 2.5,
+                life: LIFETIME * (0.6 + self.spread().abs() * 0.4),
+                color,
+            };
+            self.particles.push(particle);
+        }
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        for particle in &mut self.particles {
+            particle.x += particle.vel_x * delta;
+            particle.y += particle.vel_y * delta;
+            particle.vel_y += GRAVITY * delta;
+            particle.life -= delta * 0.016;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    // Drawn in world space
+    pub fn render(&self, ctx: &CanvasRenderingContext2d) {
+        for particle in &self.particles {
+            ctx.set_global_alpha((particle.life / LIFETIME).min(1.0));
+            ctx.set_fill_style_str(particle.color);
+            ctx.fill_rect(particle.x - SIZE / 2.0, particle.y - SIZE / 2.0, SIZE, SIZE);
+        }
+        ctx.set_global_alpha(1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_throw_sparks_that_fall_and_fade() {
+        let mut particles = Particles::default();
+        particles.handle(&GameEvent::SwordSwung);
+        assert!(particles.particles.is_empty());
+
+        particles.handle(&GameEvent::EnemyHit { x: 100.0, y: 200.0 });
+        assert_eq!(particles.particles.len(), 6);
+        for _ in 0..20 {
+            particles.update(1.0);
+        }
+        assert_eq!(particles.particles.len(), 6);
+        assert!(particles.particles.iter().all(|particle| particle.vel_y > 0.0));
+        for _ in 0..30 {
+            particles.update(1.0);
+        }
+        assert!(particles.particles.is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::Game;
+use serde::{Deserialize, Serialize};
+
+// Bump whenever a change to the game structs would break older snapshots
+pub const SAVE_VERSION: u32 = 10;
+
+#[derive(Serialize)]
+struct SaveStateRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+#[derive(Deserialize)]
+struct SaveState {
+    game: Game,
+}
+
+// Everything needed to resume a run, including generator RNG state and camera
+pub fn save_game(game: &Game) -> String {
+    let state = SaveStateRef { version: SAVE_VERSION, game };
+    serde_json::to_string(&state).unwrap_or_default()
+}
+
+pub fn load_game(json: &str) -> Result<Game, String> {
+    // Check the version first so old saves get a clear error rather than a
+    // missing field message
+    #[derive(Deserialize)]
+    struct Header {
+        version: u32,
+    }
+    let header: Header =
+        serde_json::from_str(json).map_err(|e| format!("Invalid save state: {}", e))?;
+    if header.version != SAVE_VERSION {
+        return Err(format!(
+            "Save state version {} is not supported (expected {})",
+            header.version, SAVE_VERSION
+        ));
+    }
+
+    let state: SaveState =
+        serde_json::from_str(json).map_err(|e| format!("Invalid save state: {}", e))?;
+    Ok(state.game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::KeyState;
+    use crate::inventory::ItemKind;
+    use crate::shop::Upgrades;
+    use crate::difficulty::Difficulty;
+    use crate::RunMode;
+
+    fn new_game(seed: u32) -> Game {
+        Game::new(seed, RunMode::Standard, &Upgrades::default(), Difficulty::Knight)
+    }
+
+    fn run_frames(game: &mut Game, frames: usize) {
+        let keys = KeyState::default();
+        for _ in 0..frames {
+            game.update(1.0, &keys);
+        }
+    }
+
+    #[test]
+    fn restored_game_continues_identically() {
+        let mut original = new_game(12345);
+        run_frames(&mut original, 30);
+
+        let mut restored = load_game(&save_game(&original)).unwrap();
+        run_frames(&mut original, 120);
+        run_frames(&mut restored, 120);
+        assert_eq!(save_game(&restored), save_game(&original));
+    }
+
+    #[test]
+    fn inventory_and_thrown_items_survive_a_save() {
+        let mut original = new_game(4242);
+        original.player.inventory.add(ItemKind::Bomb);
+        original.player.inventory.add(ItemKind::Bomb);
+        original.player.inventory.add(ItemKind::Potion);
+        original.use_item();
+        original.player.inventory.cycle();
+        run_frames(&mut original, 2);
+        assert_eq!(original.projectiles.len(), 1);
+
+        let restored = load_game(&save_game(&original)).unwrap();
+        assert_eq!(restored.player.inventory, original.player.inventory);
+        assert_eq!(restored.player.inventory.selected_item(), Some(ItemKind::Potion));
+        assert_eq!(restored.projectiles.len(), 1);
+    }
+
+    #[test]
+    fn crafted_snapshot_drives_generation() {
+        // Push the knight far along a fresh run and let the generator catch up
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&save_game(&new_game(99))).unwrap();
+        snapshot["game"]["player"]["x"] = 5000.0.into();
+        snapshot["game"]["player"]["y"] = 0.0.into();
+        // The goblin at the start has been killed
+        snapshot["game"]["enemies"][0]["is_alive"] = false.into();
+
+        let mut game = load_game(&snapshot.to_string()).unwrap();
+        run_frames(&mut game, 1);
+        assert!(game.camera_x >= 4600.0);
+        assert!(game.platforms.iter().any(|platform| platform.x > game.camera_x + 800.0));
+        assert!(game.platforms.iter().all(|platform| platform.x + platform.width > game.camera_x - 500.0));
+        // Dead enemies left behind aren't carried into later saves
+        assert!(game.enemies.iter().all(|enemy| enemy.x > game.camera_x - 500.0));
+    }
+
+    #[test]
+    fn rejects_other_versions_and_garbage() {
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&save_game(&new_game(1))).unwrap();
+        snapshot["version"] = (SAVE_VERSION + 1).into();
+
+        let error = load_game(&snapshot.to_string()).err().unwrap();
+        assert!(error.contains("not supported"), "{}", error);
+        assert!(load_game("{\"version\": 1}").is_err());
+        assert!(load_game("").is_err());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::achievements::{Achievements, ACHIEVEMENTS};
+use crate::highscores::{HighScoreTable, MAX_ENTRIES};
+use crate::input::{key_label, Action};
+use crate::leveling::StatChoice;
+use crate::settings::{OptionItem, Settings};
+use crate::shop::{Progress, UPGRADES};
+use crate::ui::{Anchor, Rect, Ui, DIM, GOLD, GREY, SCREEN, WHITE};
+use crate::{Game, RunMode};
+use web_sys::CanvasRenderingContext2d;
+
+// Seconds the game over banner shows before moving on to the results
+pub const GAME_OVER_DELAY: f64 = 2.0;
+// Seconds the level-up choices ignore input, so an attack mashed through
+// the kill that levelled up can't pick a stat before it is seen
+pub const LEVEL_UP_INPUT_DELAY: f64 = 0.5;
+// Seconds the reason a purchase failed stays under the armory list
+pub const SHOP_FLASH_TIME: f64 = 2.0;
+
+// Buttons on the title menu, in focus order
+pub const TITLE_ITEMS: usize = 6;
+pub const TITLE_START: usize = 0;
+pub const TITLE_MODE: usize = 1;  // Left / Right change the mode while focused
+pub const TITLE_SHOP: usize = 2;
+pub const TITLE_LEADERBOARD: usize = 3;
+pub const TITLE_ACHIEVEMENTS: usize = 4;
+pub const TITLE_OPTIONS: usize = 5;
+
+// Buttons on the pause menu
+pub const PAUSE_ITEMS: usize = 2;
+pub const PAUSE_RESUME: usize = 0;
+pub const PAUSE_OPTIONS: usize = 1;
+
+// The controls list has a row per action, then these two
+pub const CONTROLS_RESET: usize = Action::ALL.len();
+pub const CONTROLS_BACK: usize = Action::ALL.len() + 1;
+pub const CONTROLS_ITEMS: usize = Action::ALL.len() + 2;
+
+// Buttons on the results screen, in focus order
+pub const RESULTS_ITEMS: usize = 3;
+pub const RESULTS_NEW_RUN: usize = 0;
+pub const RESULTS_RETRY: usize = 1;
+pub const RESULTS_TITLE: usize = 2;
+
+// Top-level flow owned by GameEngine
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scene {
+    Title { selected: usize },  // Focused menu button
+    Playing,
+    Paused { selected: usize },
+    LevelUp { selected: usize, elapsed: f64 },  // Index into StatChoice::ALL and seconds shown
+    GameOver { elapsed: f64 },
+    Results { rank: Option<usize>, selected: usize },  // Placing on the run's high score board
+    Leaderboard,
+    Achievements,
+    Shop { selected: usize, flash: f64 },  // Index into shop::UPGRADES and seconds left on a failed purchase
+    Options { selected: usize, from_pause: bool },  // Index into OptionItem::ALL
+    Controls { selected: usize, listening: bool, from_pause: bool },  // Listening for a key to bind
+}
+
+// Full-width button in a vertical menu, centered on the screen
+fn menu_button(y: f64) -> Rect {
+    SCREEN.place(Anchor::Top, 0.0, y, 300.0, 30.0)
+}
+
+impl Scene {
+    // Reported to the page by GameEngine::state
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scene::Title { .. } => "title",
+            Scene::Playing => "playing",
+            Scene::Paused { .. } => "paused",
+            Scene::LevelUp { .. } => "level_up",
+            Scene::GameOver { .. } => "game_over",
+            Scene::Results { .. } => "results",
+            Scene::Leaderboard => "leaderboard",
+            Scene::Achievements => "achievements",
+            Scene::Shop { .. } => "shop",
+            Scene::Options { .. } => "options",
+            Scene::Controls { .. } => "controls",
+        }
+    }
+
+    // Drawn on top of the game world
+    pub fn render_overlay(
+        &self,
+        ctx: &CanvasRenderingContext2d,
+        game: &Game,
+        high_scores: &HighScoreTable,
+        progress: &Progress,
+        settings: &Settings,
+        achievements: &Achievements,
+    ) {
+        let mut ui = Ui::new();
+        match self {
+            Scene::Title { selected } => {
+                ui.panel(SCREEN, "#000000", 0.55)
+                    .label(SCREEN, Anchor::Top, 0.0, 110.0, "WASM QUEST", 7.0, GOLD)
+                    .label(SCREEN, Anchor::Top, 0.0, 170.0, "A Medieval Adventure", 2.0, GREY);
+                let labels = [
+                    "Start".to_string(),
+                    format!("Mode < {} >", game.mode.label()),
+                    "Armory".to_string(),
+                    "Hall of Fame".to_string(),
+                    "Achievements".to_string(),
+                    "Options".to_string(),
+                ];
+                for (i, label) in labels.iter().enumerate() {
+                    ui.button(menu_button(198.0 + i as f64 * 36.0), label, i == *selected, true);
+                }
+                if game.mode == RunMode::Daily {
+                    let twists = format!("Today: {}", game.modifiers.names().join(", "));
+                    ui.label(SCREEN, Anchor::Top, 0.0, 442.0, &twists, 1.0, GREY);
+                }
+                ui.label(SCREEN, Anchor::Top, 0.0, 418.0, &format!("Coins: {}", progress.coins), 2.0, GOLD)
+                    .label(SCREEN, Anchor::Bottom, 0.0, -40.0, "Up / Down: choose    Enter: select", 2.0, GREY);
+            }
+            Scene::Leaderboard => {
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 640.0, 440.0);
+                ui.panel(SCREEN, "#000000", 0.5)
+                    .dialog(dialog, "HALL OF FAME")
+                    .label(dialog, Anchor::Top, 0.0, 44.0, &format!("{} mode", game.mode.label()), 2.0, GREY);
+
+                // The daily board only covers today's seed
+                let seed = (game.mode == RunMode::Daily).then_some(game.seed);
+                let board = high_scores.board(game.mode.name(), seed);
+                if board.is_empty() {
+                    ui.label(dialog, Anchor::Center, 0.0, -40.0, "No runs yet - go make history!", 2.0, WHITE);
+                }
+                for (i, entry) in board.iter().take(MAX_ENTRIES).enumerate() {
+                    // Difficulty initial, then + if any assist was used
+                    let marks = format!(
+                        "{}{}",
+                        entry.difficulty.chars().next().unwrap_or('?').to_ascii_uppercase(),
+                        if entry.assists.is_empty() { "" } else { "+" }
+                    );
+                    let line = format!(
+                        "{:>2}. {:<12} {:>7} {:>6}m {:<2} {}",
+                        i + 1, entry.name, entry.score, entry.distance, marks, entry.date
+                    );
+                    ui.label(dialog, Anchor::TopLeft, 24.0, 75.0 + i as f64 * 24.0, &line, 2.0, WHITE);
+                }
+
+                if let Some(last) = high_scores.history().first() {
+                    let line = format!("Last run: {} - {} pts, {}m on {}",
+                        last.name, last.score, last.distance, last.date);
+                    ui.label(dialog, Anchor::Bottom, 0.0, -56.0, &line, 2.0, GREY);
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -30.0, "S/K/P: Squire, Knight, Paladin    +: assists used", 1.0, GREY)
+                    .label(dialog, Anchor::Bottom, 0.0, -14.0, "Press Enter to return", 1.0, GREY);
+            }
+            Scene::Achievements => {
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 560.0, 440.0);
+                let count = format!("{} / {} unlocked", achievements.unlocked_count(), ACHIEVEMENTS.len());
+                ui.panel(SCREEN, "#000000", 0.5)
+                    .dialog(dialog, "ACHIEVEMENTS")
+                    .label(dialog, Anchor::Top, 0.0, 44.0, &count, 2.0, GREY);
+
+                for (i, achievement) in ACHIEVEMENTS.iter().enumerate() {
+                    let row = dialog.place(Anchor::Top, 0.0, 66.0 + i as f64 * 40.0, 500.0, 36.0);
+                    let unlocked = achievements.is_unlocked(achievement);
+                    let status = match achievements.progress(achievement) {
+                        _ if unlocked => "DONE".to_string(),
+                        Some((count, goal)) => format!("{}/{}", count, goal),
+                        None => String::new(),
+                    };
+                    ui.button(row, "", false, unlocked)
+                        .label(row, Anchor::TopLeft, 14.0, 5.0, achievement.name, 2.0, if unlocked { GOLD } else { WHITE })
+                        .label(row, Anchor::BottomLeft, 14.0, -5.0, achievement.description, 1.0, GREY)
+                        .label(row, Anchor::Right, -14.0, 0.0, &status, 2.0, if unlocked { GOLD } else { DIM });
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -14.0, "Press Enter to return", 1.0, GREY);
+            }
+            Scene::Shop { selected, flash } => {
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 560.0, 440.0);
+                ui.panel(SCREEN, "#000000", 0.5)
+                    .dialog(dialog, "ARMORY")
+                    .label(dialog, Anchor::Top, 0.0, 44.0, &format!("Coins: {}", progress.coins), 2.0, GOLD);
+
+                for (i, upgrade) in UPGRADES.iter().enumerate() {
+                    let row = dialog.place(Anchor::Top, 0.0, 70.0 + i as f64 * 60.0, 500.0, 52.0);
+                    let level = progress.upgrades.level(upgrade);
+                    let price = match progress.next_cost(upgrade) {
+                        Some(cost) => format!("{} coins", cost),
+                        None => "MAX".to_string(),
+                    };
+                    let affordable = progress.next_cost(upgrade).is_some_and(|cost| cost <= progress.coins);
+
+                    ui.button(row, "", i == *selected, affordable)
+                        .label(row, Anchor::TopLeft, 30.0, 10.0,
+                            &format!("{}  {}/{}", upgrade.name, level, upgrade.costs.len()), 2.0, WHITE)
+                        .label(row, Anchor::BottomLeft, 30.0, -10.0, upgrade.description, 1.0, GREY)
+                        .label(row, Anchor::Right, -14.0, 0.0, &price, 2.0,
+                            if affordable { "#32CD32" } else { DIM });
+                }
+                if *flash > 0.0 {
+                    if let Err(reason) = progress.price(*selected) {
+                        ui.label(dialog, Anchor::Bottom, 0.0, -48.0, &reason, 2.0, "#FF6347");
+                    }
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -20.0, "Up / Down: choose    Enter: buy    Esc: back", 2.0, GREY);
+            }
+            Scene::Playing => {}
+            Scene::Paused { selected } => {
+                ui.panel(SCREEN, "#000000", 0.45)
+                    .label(SCREEN, Anchor::Center, 0.0, -60.0, "PAUSED", 6.0, WHITE);
+                for (i, label) in ["Resume", "Options"].iter().enumerate() {
+                    ui.button(menu_button(240.0 + i as f64 * 38.0), label, i == *selected, true);
+                }
+                ui.label(SCREEN, Anchor::Bottom, 0.0, -40.0, "Esc: resume", 2.0, GREY);
+            }
+            Scene::Options { selected, .. } => {
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 440.0, 484.0);
+                ui.panel(SCREEN, "#000000", 0.5).dialog(dialog, "OPTIONS");
+                for (i, item) in OptionItem::ALL.iter().enumerate() {
+                    let row = dialog.place(Anchor::Top, 0.0, 46.0 + i as f64 * 34.0, 380.0, 28.0);
+                    ui.button(row, &item.label(settings), i == *selected, true);
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -30.0,
+                        "Difficulty applies from the next run; assists mark your scores", 1.0, GREY)
+                    .label(dialog, Anchor::Bottom, 0.0, -14.0, "Left / Right: change    Enter: select", 1.0, GREY);
+            }
+            Scene::Controls { selected, listening, .. } => {
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 440.0, 480.0);
+                ui.panel(SCREEN, "#000000", 0.5).dialog(dialog, "CONTROLS");
+                for (i, action) in Action::ALL.iter().enumerate() {
+                    let row = dialog.place(Anchor::Top, 0.0, 46.0 + i as f64 * 28.0, 380.0, 24.0);
+                    let keys = if *listening && i == *selected {
+                        "Press a key...".to_string()
+                    } else {
+                        let keys: Vec<_> = settings.key_map.keys_for(*action).iter().map(|key| key_label(key)).collect();
+                        keys.join(", ")
+                    };
+                    ui.button(row, "", i == *selected, true)
+                        .label(row, Anchor::Left, 24.0, 0.0, action.name(), 2.0, WHITE)
+                        .label(row, Anchor::Right, -10.0, 0.0, &keys, 2.0, GOLD);
+                }
+                for (i, label) in [(CONTROLS_RESET, "Reset to defaults"), (CONTROLS_BACK, "Back")] {
+                    let row = dialog.place(Anchor::Top, 0.0, 46.0 + i as f64 * 28.0, 380.0, 24.0);
+                    ui.button(row, label, i == *selected, true);
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -14.0, "Enter: rebind    Esc while rebinding: cancel", 1.0, GREY);
+            }
+            Scene::LevelUp { selected, .. } => {
+                let experience = &game.player.experience;
+                let dialog = SCREEN.place(Anchor::Center, 0.0, 0.0, 420.0, 400.0);
+                ui.panel(SCREEN, "#000000", 0.4).dialog(dialog, "LEVEL UP!");
+                let title = if experience.pending > 1 {
+                    format!("Level {} - {} choices left", experience.level, experience.pending)
+                } else {
+                    format!("Level {} - choose a boon", experience.level)
+                };
+                ui.label(dialog, Anchor::Top, 0.0, 46.0, &title, 2.0, WHITE);
+
+                for (i, choice) in StatChoice::ALL.iter().enumerate() {
+                    let row = dialog.place(Anchor::Top, 0.0, 80.0 + i as f64 * 62.0, 360.0, 52.0);
+                    ui.button(row, "", i == *selected, true)
+                        .label(row, Anchor::Top, 0.0, 10.0, choice.name(), 2.0, GOLD)
+                        .label(row, Anchor::Bottom, 0.0, -10.0, choice.description(), 1.0, GREY);
+                }
+                ui.label(dialog, Anchor::Bottom, 0.0, -20.0, "Up / Down: choose    Enter: take it", 2.0, GREY);
+            }
+            Scene::GameOver { elapsed } => {
+                ui.panel(SCREEN, "#000000", (elapsed / GAME_OVER_DELAY).min(1.0) * 0.5)
+                    .label(SCREEN, Anchor::Center, 0.0, 0.0, "GAME OVER", 6.0, "#FF0000");
+            }
+            Scene::Results { rank, selected } => {
+                ui.panel(SCREEN, "#000000", 0.7);
+                if let Some(rank) = rank {
+                    ui.label(SCREEN, Anchor::Top, 0.0, 20.0, &format!("NEW HIGH SCORE! #{}", rank), 3.0, "#32CD32");
+                }
+                let summary = game.score.summary();
+                ui.label(SCREEN, Anchor::Top, 0.0, 55.0, "RUN COMPLETE", 5.0, GOLD)
+                    .label(SCREEN, Anchor::Top, 0.0, 100.0, &format!("Score: {}", summary.score), 3.0, WHITE);
+                let mut lines = vec![
+                    format!("Difficulty: {}", game.difficulty.name()),
+                    format!("Distance: {}m", summary.distance),
+                    format!("Goblins defeated: {} ({} in the air)", summary.kills, summary.air_kills),
+                    format!("Hearts collected: {}", summary.hearts_collected),
+                    format!("Damage taken: {} hearts", summary.damage_taken),
+                    format!("Best combo: {}    Level reached: {}", summary.max_combo, game.player.experience.level),
+                    format!("Checkpoints reached: {}", game.checkpoints_reached),
+                    format!("Coins collected: {}", game.coins_collected),
+                    format!("Time: {:.1}s", summary.time),
+                    format!("Seed: {}", game.seed),
+                ];
+                if game.assists_used.any() {
+                    lines.insert(1, format!("Assists: {}", game.assists_used.names().join(", ")));
+                }
+                for (i, line) in lines.iter().enumerate() {
+                    ui.label(SCREEN, Anchor::Top, 0.0, 130.0 + i as f64 * 21.0, line, 2.0, WHITE);
+                }
+                for (i, label) in ["New Run", "Retry Seed", "Title"].iter().enumerate() {
+                    let button = SCREEN.place(Anchor::Bottom, (i as f64 - 1.0) * 190.0, -25.0, 170.0, 30.0);
+                    ui.button(button, label, i == *selected, true);
+                }
+            }
+        }
+        ui.render(ctx);
+    }
+}
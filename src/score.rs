@@ -0,0 +1,177 @@
+use crate::effects::PickupKind;
+use crate::events::GameEvent;
+use serde::{Deserialize, Serialize};
+
+// Points awarded per event, before the combo multiplier
+const KILL_POINTS: u64 = 100;
+const AIR_KILL_BONUS: u64 = 50;
+const HEART_POINTS: u64 = 25;
+const STREAK_POINTS: u64 = 50;
+const STREAK_INTERVAL: f64 = 10.0;  // Seconds without damage per streak bonus
+
+// Combo tuning (seconds)
+const COMBO_WINDOW: f64 = 3.0;  // Time after a kill before the combo starts to decay
+const COMBO_DECAY_STEP: f64 = 0.5;  // Time per combo step lost once decaying
+const MAX_MULTIPLIER: f64 = 5.0;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub score: u64,
+    pub distance: u32,  // Meters
+    pub kills: u32,
+    pub air_kills: u32,
+    pub hearts_collected: u32,
+    pub time: f64,  // Seconds
+    pub damage_taken: f64,  // Hearts
+    pub max_combo: u32,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ScoreTracker {
+    points: u64,  // Event points; distance is added on top
+    distance: u32,
+    combo: u32,
+    combo_timer: f64,
+    no_damage_time: f64,
+    kills: u32,
+    air_kills: u32,
+    hearts_collected: u32,
+    time: f64,
+    damage_taken: f64,  // Quarter hearts
+    max_combo: u32,
+}
+
+impl ScoreTracker {
+    pub fn score(&self) -> u64 {
+        self.points + self.distance as u64
+    }
+
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    // 1x for the first kill, then +0.5x per chained kill
+    pub fn multiplier(&self) -> f64 {
+        (1.0 + self.combo.saturating_sub(1) as f64 * 0.5).min(MAX_MULTIPLIER)
+    }
+
+    fn award(&mut self, points: u64) {
+        self.points += (points as f64 * self.multiplier()).round() as u64;
+    }
+
+    pub fn update(&mut self, dt: f64, distance_meters: u32) {
+        self.time += dt;
+        self.distance = self.distance.max(distance_meters);
+
+        if self.combo > 0 {
+            self.combo_timer -= dt;
+            if self.combo_timer <= 0.0 {
+                self.combo -= 1;
+                self.combo_timer = COMBO_DECAY_STEP;
+            }
+        }
+
+        self.no_damage_time += dt;
+        if self.no_damage_time >= STREAK_INTERVAL {
+            self.no_damage_time -= STREAK_INTERVAL;
+            self.award(STREAK_POINTS);
+        }
+    }
+
+    pub fn enemy_killed(&mut self, airborne: bool) {
+        self.combo += 1;
+        self.combo_timer = COMBO_WINDOW;
+        self.max_combo = self.max_combo.max(self.combo);
+        self.kills += 1;
+
+        self.award(KILL_POINTS);
+        if airborne {
+            self.air_kills += 1;
+            self.award(AIR_KILL_BONUS);
+        }
+    }
+
+    pub fn heart_collected(&mut self) {
+        self.hearts_collected += 1;
+        self.award(HEART_POINTS);
+    }
+
+    // Getting hit breaks both the combo and the no-damage streak
+    pub fn player_damaged(&mut self, quarters: f64) {
+        self.damage_taken += quarters;
+        self.combo = 0;
+        self.combo_timer = 0.0;
+        self.no_damage_time = 0.0;
+    }
+
+    pub fn handle(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::EnemyKilled { airborne, .. } => self.enemy_killed(airborne),
+            GameEvent::PickupCollected { kind: PickupKind::Heart, .. } => self.heart_collected(),
+            GameEvent::PlayerDamaged { quarters, .. } => self.player_damaged(quarters),
+            _ => {}
+        }
+    }
+
+    pub fn summary(&self) -> RunSummary {
+        RunSummary {
+            score: self.score(),
+            distance: self.distance,
+            kills: self.kills,
+            air_kills: self.air_kills,
+            hearts_collected: self.hearts_collected,
+            time: self.time,
+            damage_taken: self.damage_taken / 4.0,
+            max_combo: self.max_combo,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chained_kills_raise_the_multiplier() {
+        let mut score = ScoreTracker::default();
+        score.enemy_killed(false);
+        score.enemy_killed(false);
+        score.enemy_killed(true);
+
+        // 100 + 150 + (100 + 50) * 2.0
+        assert_eq!(score.score(), 550);
+        assert_eq!(score.summary().max_combo, 3);
+        assert_eq!(score.summary().air_kills, 1);
+    }
+
+    #[test]
+    fn combo_decays_and_damage_resets_it() {
+        let mut score = ScoreTracker::default();
+        score.enemy_killed(false);
+        score.enemy_killed(false);
+
+        score.update(COMBO_WINDOW - 0.1, 0);
+        assert_eq!(score.combo(), 2);
+        score.update(0.2, 0);
+        assert_eq!(score.combo(), 1);
+        score.update(COMBO_DECAY_STEP, 0);
+        assert_eq!(score.combo(), 0);
+
+        score.enemy_killed(false);
+        score.player_damaged(1.0);
+        assert_eq!(score.multiplier(), 1.0);
+        assert_eq!(score.summary().damage_taken, 0.25);
+    }
+
+    #[test]
+    fn streak_bonus_needs_uninterrupted_time() {
+        let mut score = ScoreTracker::default();
+        score.update(STREAK_INTERVAL - 1.0, 12);
+        score.player_damaged(1.0);
+        score.update(STREAK_INTERVAL - 1.0, 30);
+        assert_eq!(score.score(), 30);
+
+        score.update(1.0, 30);
+        assert_eq!(score.score(), 30 + STREAK_POINTS);
+    }
+}
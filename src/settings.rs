@@ -0,0 +1,209 @@
+use crate::difficulty::{Assists, Difficulty};
+use crate::input::KeyMap;
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "settings";
+// Where the page kept custom bindings before they moved into the settings
+const LEGACY_KEY_MAP_KEY: &str = "keyMap";
+
+pub const VOLUME_STEPS: u32 = 10;
+pub const MAX_PIXEL_SCALE: u32 = 3;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub music_volume: u32,  // 0..=VOLUME_STEPS
+    pub sfx_volume: u32,
+    pub screen_shake: bool,
+    pub difficulty: Difficulty,  // Takes effect from the next run
+    pub assists: Assists,
+    pub show_fps: bool,
+    pub pixel_scale: u32,  // Canvas size multiplier, applied by the page
+    pub key_map: KeyMap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            music_volume: 7,
+            sfx_volume: 8,
+            screen_shake: true,
+            difficulty: Difficulty::default(),
+            assists: Assists::default(),
+            show_fps: false,
+            pixel_scale: 1,
+            key_map: KeyMap::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(storage: &dyn Storage) -> Self {
+        match storage.get(STORAGE_KEY) {
+            Some(json) => Settings::from_json(&json).unwrap_or_default(),
+            None => {
+                let key_map = storage
+                    .get(LEGACY_KEY_MAP_KEY)
+                    .and_then(|json| KeyMap::from_json(&json).ok())
+                    .unwrap_or_default();
+                Settings { key_map, ..Settings::default() }
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set(STORAGE_KEY, &self.to_json());
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    // Missing fields keep their defaults and out of range values are clamped
+    pub fn from_json(json: &str) -> Result<Settings, String> {
+        let mut settings: Settings =
+            serde_json::from_str(json).map_err(|e| format!("Invalid settings: {}", e))?;
+        settings.music_volume = settings.music_volume.min(VOLUME_STEPS);
+        settings.sfx_volume = settings.sfx_volume.min(VOLUME_STEPS);
+        settings.pixel_scale = settings.pixel_scale.clamp(1, MAX_PIXEL_SCALE);
+        // Fills in actions added since the bindings were saved
+        settings.key_map = KeyMap::from_json(&settings.key_map.to_json())?;
+        Ok(settings)
+    }
+}
+
+// Rows of the options menu, in focus order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OptionItem {
+    MusicVolume,
+    SfxVolume,
+    ScreenShake,
+    Difficulty,
+    SlowMotion,
+    InfiniteHealth,
+    AutoAttack,
+    ShowFps,
+    PixelScale,
+    Controls,
+    Back,
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "On" } else { "Off" }
+}
+
+// Steps a value within 0..len, wrapping at either end
+fn cycle(index: usize, len: usize, step: i32) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+impl OptionItem {
+    pub const ALL: [OptionItem; 11] = [
+        OptionItem::MusicVolume,
+        OptionItem::SfxVolume,
+        OptionItem::ScreenShake,
+        OptionItem::Difficulty,
+        OptionItem::SlowMotion,
+        OptionItem::InfiniteHealth,
+        OptionItem::AutoAttack,
+        OptionItem::ShowFps,
+        OptionItem::PixelScale,
+        OptionItem::Controls,
+        OptionItem::Back,
+    ];
+
+    pub fn label(self, settings: &Settings) -> String {
+        match self {
+            OptionItem::MusicVolume => format!("Music  < {} >", settings.music_volume),
+            OptionItem::SfxVolume => format!("Sound  < {} >", settings.sfx_volume),
+            OptionItem::ScreenShake => format!("Screen shake  {}", on_off(settings.screen_shake)),
+            OptionItem::Difficulty => format!("Difficulty  < {} >", settings.difficulty.name()),
+            OptionItem::SlowMotion => format!("Slow motion  {}", on_off(settings.assists.slow_motion)),
+            OptionItem::InfiniteHealth => format!("Infinite health  {}", on_off(settings.assists.infinite_health)),
+            OptionItem::AutoAttack => format!("Auto-attack  {}", on_off(settings.assists.auto_attack)),
+            OptionItem::ShowFps => format!("Show FPS  {}", on_off(settings.show_fps)),
+            OptionItem::PixelScale => format!("Pixel scale  < {}x >", settings.pixel_scale),
+            OptionItem::Controls => "Controls".to_string(),
+            OptionItem::Back => "Back".to_string(),
+        }
+    }
+
+    // Applies a Left (-1) or Right/Confirm (+1) press. Numbers stop at
+    // their limits, choices wrap around. Returns false for rows that
+    // don't hold a value.
+    pub fn adjust(self, settings: &mut Settings, step: i32) -> bool {
+        match self {
+            OptionItem::MusicVolume => {
+                settings.music_volume = settings.music_volume.saturating_add_signed(step).min(VOLUME_STEPS);
+            }
+            OptionItem::SfxVolume => {
+                settings.sfx_volume = settings.sfx_volume.saturating_add_signed(step).min(VOLUME_STEPS);
+            }
+            OptionItem::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            OptionItem::Difficulty => {
+                let index = Difficulty::ALL.iter().position(|d| *d == settings.difficulty).unwrap_or(0);
+                settings.difficulty = Difficulty::ALL[cycle(index, Difficulty::ALL.len(), step)];
+            }
+            OptionItem::SlowMotion => settings.assists.slow_motion = !settings.assists.slow_motion,
+            OptionItem::InfiniteHealth => settings.assists.infinite_health = !settings.assists.infinite_health,
+            OptionItem::AutoAttack => settings.assists.auto_attack = !settings.assists.auto_attack,
+            OptionItem::ShowFps => settings.show_fps = !settings.show_fps,
+            OptionItem::PixelScale => {
+                settings.pixel_scale = settings.pixel_scale.saturating_add_signed(step).clamp(1, MAX_PIXEL_SCALE);
+            }
+            OptionItem::Controls | OptionItem::Back => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Action;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn adjusting_clamps_numbers_and_wraps_choices() {
+        let mut settings = Settings::default();
+        for _ in 0..20 {
+            OptionItem::MusicVolume.adjust(&mut settings, 1);
+            OptionItem::PixelScale.adjust(&mut settings, -1);
+        }
+        assert_eq!(settings.music_volume, VOLUME_STEPS);
+        assert_eq!(settings.pixel_scale, 1);
+
+        OptionItem::Difficulty.adjust(&mut settings, 1);
+        assert_eq!(settings.difficulty, Difficulty::Paladin);
+        OptionItem::Difficulty.adjust(&mut settings, 1);
+        assert_eq!(settings.difficulty, Difficulty::Squire);
+        OptionItem::Difficulty.adjust(&mut settings, -1);
+        assert_eq!(settings.difficulty, Difficulty::Paladin);
+
+        assert!(OptionItem::ScreenShake.adjust(&mut settings, -1));
+        assert!(!settings.screen_shake);
+        assert!(!OptionItem::Controls.adjust(&mut settings, 1));
+    }
+
+    #[test]
+    fn settings_persist_and_pick_up_old_key_maps() {
+        let mut storage = MemoryStorage::default();
+        let mut legacy = KeyMap::default();
+        legacy.rebind(Action::Jump, "k");
+        storage.set(LEGACY_KEY_MAP_KEY, &legacy.to_json());
+
+        let mut settings = Settings::load(&storage);
+        assert_eq!(settings.key_map.action_for("k"), Some(Action::Jump));
+
+        settings.show_fps = true;
+        settings.save(&mut storage);
+        assert_eq!(Settings::load(&storage), settings);
+
+        let partial = Settings::from_json("{\"sfx_volume\": 99, \"pixel_scale\": 0}").unwrap();
+        assert_eq!(partial.sfx_volume, VOLUME_STEPS);
+        assert_eq!(partial.pixel_scale, 1);
+        assert_eq!(partial.music_volume, Settings::default().music_volume);
+        assert!(Settings::from_json("{\"show_fps\": \"yes\"}").is_err());
+    }
+}
@@ -0,0 +1,259 @@
+// 8-bit sound effects synthesized from small parameter presets, in the
+// spirit of sfxr. Pure Rust so the buffers can be checked in native tests;
+// audio.rs hands them to Web Audio.
+
+use crate::events::GameEvent;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Noise,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SfxParams {
+    pub wave: Waveform,
+    pub start_freq: f64,  // Hz
+    pub end_freq: f64,  // Slid to exponentially over the whole sound
+    pub duty: f64,  // Fraction of each square wave period spent high
+    pub pitch_jump: f64,  // Frequency multiplier applied from jump_time on
+    pub jump_time: f64,  // Seconds
+    pub attack: f64,  // Seconds
+    pub sustain: f64,
+    pub decay: f64,
+    pub volume: f64,  // 0.0..1.0
+}
+
+const DEFAULT: SfxParams = SfxParams {
+    wave: Waveform::Square,
+    start_freq: 440.0,
+    end_freq: 440.0,
+    duty: 0.5,
+    pitch_jump: 1.0,
+    jump_time: 0.0,
+    attack: 0.0,
+    sustain: 0.05,
+    decay: 0.1,
+    volume: 0.5,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    Jump,
+    Swing,
+    Hit,  // An enemy takes a blow
+    Hurt,  // The knight takes a blow
+    Pickup,
+    Coin,
+    Explosion,
+    Death,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 8] = [
+        Sfx::Jump,
+        Sfx::Swing,
+        Sfx::Hit,
+        Sfx::Hurt,
+        Sfx::Pickup,
+        Sfx::Coin,
+        Sfx::Explosion,
+        Sfx::Death,
+    ];
+
+    pub fn for_event(event: &GameEvent) -> Option<Sfx> {
+        let sound = match event {
+            GameEvent::PlayerJumped => Sfx::Jump,
+            GameEvent::SwordSwung => Sfx::Swing,
+            GameEvent::EnemyHit { .. } => Sfx::Hit,
+            GameEvent::PlayerDamaged { .. } => Sfx::Hurt,
+            GameEvent::PickupCollected { .. } => Sfx::Pickup,
+            GameEvent::CoinCollected => Sfx::Coin,
+            GameEvent::BombExploded { .. } => Sfx::Explosion,
+            GameEvent::PlayerDied => Sfx::Death,
+            _ => return None,
+        };
+        Some(sound)
+    }
+
+    pub fn params(self) -> SfxParams {
+        match self {
+            Sfx::Jump => SfxParams {
+                start_freq: 280.0,
+                end_freq: 620.0,
+                duty: 0.25,
+                sustain: 0.06,
+                decay: 0.12,
+                volume: 0.4,
+                ..DEFAULT
+            },
+            Sfx::Swing => SfxParams {
+                wave: Waveform::Noise,
+                start_freq: 2400.0,
+                end_freq: 900.0,
+                attack: 0.01,
+                sustain: 0.03,
+                decay: 0.08,
+                volume: 0.3,
+                ..DEFAULT
+            },
+            Sfx::Hit => SfxParams {
+                wave: Waveform::Noise,
+                start_freq: 1200.0,
+                end_freq: 300.0,
+                sustain: 0.02,
+                decay: 0.12,
+                ..DEFAULT
+            },
+            Sfx::Hurt => SfxParams {
+                start_freq: 420.0,
+                end_freq: 160.0,
+                duty: 0.35,
+                sustain: 0.05,
+                decay: 0.18,
+                ..DEFAULT
+            },
+            Sfx::Pickup => SfxParams {
+                start_freq: 660.0,
+                end_freq: 660.0,
+                pitch_jump: 1.5,
+                jump_time: 0.07,
+                sustain: 0.12,
+                decay: 0.15,
+                volume: 0.4,
+                ..DEFAULT
+            },
+            Sfx::Coin => SfxParams {
+                start_freq: 990.0,
+                end_freq: 990.0,
+                duty: 0.25,
+                pitch_jump: 1.33,
+                jump_time: 0.04,
+                sustain: 0.05,
+                decay: 0.08,
+                volume: 0.25,
+                ..DEFAULT
+            },
+            Sfx::Explosion => SfxParams {
+                wave: Waveform::Noise,
+                start_freq: 700.0,
+                end_freq: 60.0,
+                sustain: 0.1,
+                decay: 0.5,
+                volume: 0.6,
+                ..DEFAULT
+            },
+            Sfx::Death => SfxParams {
+                wave: Waveform::Triangle,
+                start_freq: 520.0,
+                end_freq: 70.0,
+                sustain: 0.4,
+                decay: 0.5,
+                volume: 0.6,
+                ..DEFAULT
+            },
+        }
+    }
+}
+
+// Same LCG constants as the level generator; the seed is fixed so every
+// rendering of a noise preset is identical
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(1103515245).wrapping_add(12345);
+        ((self.0 >> 16) & 0x7fff) as f64 / 16383.5 - 1.0
+    }
+}
+
+// Renders a preset to mono samples in -1.0..=1.0
+pub fn synthesize(params: &SfxParams, sample_rate: u32) -> Vec<f32> {
+    let rate = sample_rate as f64;
+    let duration = params.attack + params.sustain + params.decay;
+    let count = (duration * rate) as usize;
+    let mut samples = Vec::with_capacity(count);
+
+    let mut noise = Noise(1);
+    let mut noise_value = noise.next();
+    let mut phase = 0.0;
+    for i in 0..count {
+        let t = i as f64 / rate;
+
+        let mut freq = params.start_freq * (params.end_freq / params.start_freq).powf(t / duration);
+        if params.pitch_jump != 1.0 && t >= params.jump_time {
+            freq *= params.pitch_jump;
+        }
+        phase += freq / rate;
+        if phase >= 1.0 {
+            phase -= phase.floor();
+            // Noise holds each random value for one period, so its pitch slides too
+            noise_value = noise.next();
+        }
+
+        let wave = match params.wave {
+            Waveform::Square => if phase < params.duty { 1.0 } else { -1.0 },
+            Waveform::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+            Waveform::Noise => noise_value,
+        };
+
+        let envelope = if t < params.attack {
+            t / params.attack
+        } else if t < params.attack + params.sustain {
+            1.0
+        } else {
+            1.0 - (t - params.attack - params.sustain) / params.decay
+        };
+
+        samples.push((wave * envelope * params.volume) as f32);
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // FNV-1a over the samples as 16-bit PCM
+    fn checksum(samples: &[f32]) -> u32 {
+        let mut hash: u32 = 0x811c9dc5;
+        for sample in samples {
+            let pcm = (sample.clamp(-1.0, 1.0) * 32767.0) as i16;
+            for byte in pcm.to_le_bytes() {
+                hash ^= byte as u32;
+                hash = hash.wrapping_mul(0x01000193);
+            }
+        }
+        hash
+    }
+
+    #[test]
+    fn presets_render_bounded_buffers_that_fade_out() {
+        for sfx in Sfx::ALL {
+            let params = sfx.params();
+            let samples = synthesize(&params, 44100);
+            let expected = ((params.attack + params.sustain + params.decay) * 44100.0) as usize;
+            assert_eq!(samples.len(), expected, "{:?}", sfx);
+            assert!(samples.iter().all(|s| s.abs() <= params.volume as f32), "{:?}", sfx);
+            assert!(samples.iter().any(|s| s.abs() > 0.1), "{:?} is silent", sfx);
+            assert!(samples.last().unwrap().abs() < 0.01, "{:?} clicks at the end", sfx);
+        }
+    }
+
+    #[test]
+    fn buffers_match_their_checksums() {
+        let checksums: Vec<_> = Sfx::ALL
+            .iter()
+            .map(|sfx| checksum(&synthesize(&sfx.params(), 44100)))
+            .collect();
+        // Regenerate these when a preset is tuned on purpose
+        assert_eq!(
+            checksums,
+            vec![
+                3667771514, 2794677750, 1347190010, 556534532, 3688699192, 2341317440, 2586354682,
+                3980663913,
+            ]
+        );
+    }
+}
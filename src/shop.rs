@@ -0,0 +1,188 @@
+use crate::inventory::ItemKind;
+use crate::storage::Storage;
+use crate::{Ability, Player};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STORAGE_KEY: &str = "progress";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpgradeEffect {
+    MaxHealth(f64),  // Quarter hearts per level
+    SwordCooldown(f64),  // Seconds taken off each swing per level
+    Invincibility(f64),  // Seconds added to the i-frames after a hit per level
+    StartingGear(&'static [Ability]),  // Ability granted from the start at each level
+    StartingItems(&'static [ItemKind]),  // Item packed at each level
+}
+
+pub struct UpgradeDef {
+    pub id: &'static str,  // Key in the saved progress, never shown
+    pub name: &'static str,
+    pub description: &'static str,
+    pub costs: &'static [u64],  // Price of each level; the length is the max level
+    pub effect: UpgradeEffect,
+}
+
+pub const UPGRADES: [UpgradeDef; 5] = [
+    UpgradeDef {
+        id: "heart",
+        name: "Heart Container",
+        description: "+1 max heart",
+        costs: &[50, 120, 250],
+        effect: UpgradeEffect::MaxHealth(4.0),
+    },
+    UpgradeDef {
+        id: "sword",
+        name: "Whetstone",
+        description: "Faster sword swings",
+        costs: &[40, 100, 200],
+        effect: UpgradeEffect::SwordCooldown(0.05),
+    },
+    UpgradeDef {
+        id: "iframes",
+        name: "Blessed Mail",
+        description: "Longer invincibility after a hit",
+        costs: &[60, 150],
+        effect: UpgradeEffect::Invincibility(0.25),
+    },
+    UpgradeDef {
+        id: "gear",
+        name: "Traveler's Gear",
+        description: "Start with Double Jump, then Dash",
+        costs: &[150, 300],
+        effect: UpgradeEffect::StartingGear(&[Ability::DoubleJump, Ability::Dash]),
+    },
+    UpgradeDef {
+        id: "supplies",
+        name: "Supply Pack",
+        description: "Start with a potion, then daggers",
+        costs: &[80, 160],
+        effect: UpgradeEffect::StartingItems(&[ItemKind::Potion, ItemKind::Dagger]),
+    },
+];
+
+// Purchased level of each upgrade, keyed by id so the table can be reordered
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Upgrades {
+    levels: BTreeMap<String, u32>,
+}
+
+impl Upgrades {
+    pub fn level(&self, upgrade: &UpgradeDef) -> u32 {
+        let level = self.levels.get(upgrade.id).copied().unwrap_or(0);
+        level.min(upgrade.costs.len() as u32)
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        for upgrade in &UPGRADES {
+            let level = self.level(upgrade);
+            match upgrade.effect {
+                UpgradeEffect::MaxHealth(quarters) => {
+                    player.raise_max_health(quarters * level as f64);
+                }
+                UpgradeEffect::SwordCooldown(seconds) => {
+                    player.attack_duration -= seconds * level as f64;
+                }
+                UpgradeEffect::Invincibility(seconds) => {
+                    player.iframe_duration += seconds * level as f64;
+                }
+                UpgradeEffect::StartingGear(abilities) => {
+                    for &ability in abilities.iter().take(level as usize) {
+                        player.abilities.unlock(ability);
+                    }
+                }
+                UpgradeEffect::StartingItems(items) => {
+                    for &item in items.iter().take(level as usize) {
+                        player.inventory.add(item);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Everything carried over between runs
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Progress {
+    pub coins: u64,
+    pub upgrades: Upgrades,
+}
+
+impl Progress {
+    pub fn load(storage: &dyn Storage) -> Self {
+        storage
+            .get(STORAGE_KEY)
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn Storage) {
+        storage.set(STORAGE_KEY, &serde_json::to_string(self).unwrap_or_default());
+    }
+
+    // None once the upgrade is maxed out
+    pub fn next_cost(&self, upgrade: &UpgradeDef) -> Option<u64> {
+        upgrade.costs.get(self.upgrades.level(upgrade) as usize).copied()
+    }
+
+    // Cost of the upgrade's next level, or why it can't be bought yet
+    pub fn price(&self, index: usize) -> Result<u64, String> {
+        let upgrade = UPGRADES.get(index).ok_or("No such upgrade")?;
+        let cost = self.next_cost(upgrade).ok_or("Already at max level")?;
+        if self.coins < cost {
+            return Err(format!("Needs {} more coins", cost - self.coins));
+        }
+        Ok(cost)
+    }
+
+    // Returns the new level
+    pub fn buy(&mut self, index: usize) -> Result<u32, String> {
+        let cost = self.price(index)?;
+        let upgrade = &UPGRADES[index];
+        self.coins -= cost;
+        let level = self.upgrades.level(upgrade) + 1;
+        self.upgrades.levels.insert(upgrade.id.to_string(), level);
+        Ok(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn buying_spends_coins_until_maxed() {
+        let mut progress = Progress { coins: 200, ..Progress::default() };
+        assert_eq!(progress.buy(2), Ok(1));
+        assert_eq!(progress.coins, 140);
+        assert!(progress.buy(2).unwrap_err().contains("10 more"));
+
+        progress.coins = 1000;
+        assert_eq!(progress.buy(2), Ok(2));
+        assert!(progress.buy(2).is_err());
+        assert_eq!(progress.next_cost(&UPGRADES[2]), None);
+        assert!(progress.buy(UPGRADES.len()).is_err());
+    }
+
+    #[test]
+    fn upgrades_apply_to_a_new_knight_and_persist() {
+        let mut progress = Progress { coins: 1000, ..Progress::default() };
+        progress.buy(0).unwrap();
+        progress.buy(1).unwrap();
+        progress.buy(3).unwrap();
+
+        let mut storage = MemoryStorage::default();
+        progress.save(&mut storage);
+        let restored = Progress::load(&storage);
+        assert_eq!(restored, progress);
+
+        let player = Player::with_upgrades(&restored.upgrades);
+        assert_eq!(player.max_health, 32.0);
+        assert_eq!(player.health, 32.0);
+        assert!((player.attack_duration - 0.35).abs() < 1e-9);
+        assert_eq!(player.iframe_duration, 1.0);
+        assert!(player.abilities.has(Ability::DoubleJump));
+        assert!(!player.abilities.has(Ability::Dash));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::font;
+use crate::input::{Action, ACTION_COUNT};
+use web_sys::CanvasRenderingContext2d;
+
+struct TouchButton {
+    action: Action,
+    x: f64,  // Center, in canvas pixels
+    y: f64,
+    radius: f64,
+    label: &'static str,
+}
+
+impl TouchButton {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        // Generous hit area so thumbs don't slip off the edge
+        let reach = self.radius * 1.25;
+        let (dx, dy) = (x - self.x, y - self.y);
+        dx * dx + dy * dy <= reach * reach
+    }
+}
+
+// On-canvas virtual D-pad and action buttons with multi-touch tracking
+pub struct TouchControls {
+    buttons: Vec<TouchButton>,
+    touches: Vec<(i32, Option<usize>)>,  // Touch identifier and the button under it
+    pub visible: bool,  // Only drawn once the player has touched the screen
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        let button = |action, x, y, radius, label| TouchButton { action, x, y, radius, label };
+        TouchControls {
+            buttons: vec![
+                button(Action::Left, 60.0, 420.0, 28.0, "<"),
+                button(Action::Right, 150.0, 420.0, 28.0, ">"),
+                button(Action::Jump, 105.0, 375.0, 28.0, "^"),
+                button(Action::Down, 105.0, 465.0, 28.0, "v"),
+                button(Action::Jump, 730.0, 420.0, 34.0, "A"),
+                button(Action::Attack, 650.0, 450.0, 34.0, "B"),
+                button(Action::Block, 660.0, 370.0, 22.0, "BLK"),
+                button(Action::Dash, 740.0, 340.0, 22.0, "DSH"),
+                button(Action::UseItem, 580.0, 400.0, 22.0, "ITM"),
+                button(Action::CycleItem, 590.0, 340.0, 18.0, "<>"),
+                button(Action::Pause, 770.0, 70.0, 18.0, "II"),
+            ],
+            touches: Vec::new(),
+            visible: false,
+        }
+    }
+}
+
+impl TouchControls {
+    fn hit_test(&self, x: f64, y: f64) -> Option<usize> {
+        self.buttons.iter().position(|button| button.contains(x, y))
+    }
+
+    // Returns true if the touch landed on a button
+    pub fn touch_start(&mut self, id: i32, x: f64, y: f64) -> bool {
+        self.visible = true;
+        let hit = self.hit_test(x, y);
+        self.touches.retain(|(touch_id, _)| *touch_id != id);
+        self.touches.push((id, hit));
+        hit.is_some()
+    }
+
+    // Sliding a thumb from one button to another switches between them
+    pub fn touch_move(&mut self, id: i32, x: f64, y: f64) -> bool {
+        let hit = self.hit_test(x, y);
+        match self.touches.iter_mut().find(|(touch_id, _)| *touch_id == id) {
+            Some(touch) => {
+                touch.1 = hit;
+                hit.is_some()
+            }
+            None => false,
+        }
+    }
+
+    pub fn touch_end(&mut self, id: i32) {
+        self.touches.retain(|(touch_id, _)| *touch_id != id);
+    }
+
+    fn is_pressed(&self, index: usize) -> bool {
+        self.touches.iter().any(|(_, button)| *button == Some(index))
+    }
+
+    pub fn held_actions(&self) -> [bool; ACTION_COUNT] {
+        let mut held = [false; ACTION_COUNT];
+        for (index, button) in self.buttons.iter().enumerate() {
+            if self.is_pressed(index) {
+                held[button.action as usize] = true;
+            }
+        }
+        held
+    }
+
+    pub fn render(&self, ctx: &CanvasRenderingContext2d) {
+        if !self.visible {
+            return;
+        }
+
+        ctx.save();
+        for (index, button) in self.buttons.iter().enumerate() {
+            let pressed = self.is_pressed(index);
+            ctx.set_global_alpha(if pressed { 0.7 } else { 0.35 });
+            ctx.set_fill_style_str(if pressed { "#FFD700" } else { "#2E3440" });
+            ctx.begin_path();
+            ctx.arc(button.x, button.y, button.radius, 0.0, std::f64::consts::PI * 2.0).unwrap();
+            ctx.fill();
+
+            ctx.set_global_alpha(0.9);
+            ctx.set_stroke_style_str("#C0C0C0");
+            ctx.set_line_width(2.0);
+            ctx.stroke();
+
+            let scale = if button.radius > 25.0 { 3.0 } else { 2.0 };
+            let (w, h) = font::measure(button.label, scale);
+            font::draw_text(ctx, button.label, button.x - w / 2.0, button.y - h / 2.0, scale, "#FFFFFF");
+        }
+        ctx.restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(controls: &TouchControls, action: Action) -> bool {
+        controls.held_actions()[action as usize]
+    }
+
+    #[test]
+    fn presses_reach_past_the_drawn_edge_and_misses_hold_nothing() {
+        let mut controls = TouchControls::default();
+        // 33px from the left arrow's center: outside its 28px circle but
+        // inside the 1.25x reach
+        assert!(controls.touch_start(1, 93.0, 420.0));
+        assert!(held(&controls, Action::Left));
+        assert!(controls.visible);
+
+        controls.touch_end(1);
+        assert!(!controls.touch_start(2, 400.0, 200.0));
+        assert!(!controls.touch_start(3, 96.0, 420.0));
+        assert_eq!(controls.held_actions(), [false; ACTION_COUNT]);
+    }
+
+    #[test]
+    fn thumbs_slide_between_buttons_and_release_independently() {
+        let mut controls = TouchControls::default();
+        controls.touch_start(1, 60.0, 420.0);
+        assert!(controls.touch_move(1, 150.0, 420.0));
+        assert!(!held(&controls, Action::Left));
+        assert!(held(&controls, Action::Right));
+
+        // A second thumb on attack while the first keeps running
+        controls.touch_start(2, 650.0, 450.0);
+        assert!(held(&controls, Action::Right));
+        assert!(held(&controls, Action::Attack));
+
+        controls.touch_end(1);
+        assert!(!held(&controls, Action::Right));
+        assert!(held(&controls, Action::Attack));
+
+        // Moves of a touch that already ended are ignored
+        assert!(!controls.touch_move(1, 60.0, 420.0));
+        assert!(!held(&controls, Action::Left));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::font;
+use crate::input::{Action, KeyState};
+use web_sys::CanvasRenderingContext2d;
+
+// Shared palette so menus and the HUD agree
+pub const WHITE: &str = "#FFFFFF";
+pub const GREY: &str = "#C0C0C0";
+pub const GOLD: &str = "#FFD700";
+pub const DIM: &str = "#808080";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+pub const SCREEN: Rect = Rect { x: 0.0, y: 0.0, w: 800.0, h: 500.0 };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Fraction of the width and height the anchor sits at
+    fn factors(self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl Rect {
+    pub const fn new(x: f64, y: f64, w: f64, h: f64) -> Self {
+        Rect { x, y, w, h }
+    }
+
+    // The anchor's point on this rect, nudged by an offset
+    pub fn point(&self, anchor: Anchor, dx: f64, dy: f64) -> (f64, f64) {
+        let (fx, fy) = anchor.factors();
+        (self.x + self.w * fx + dx, self.y + self.h * fy + dy)
+    }
+
+    // A w x h child lined up so its own anchor point sits on ours
+    pub fn place(&self, anchor: Anchor, dx: f64, dy: f64, w: f64, h: f64) -> Rect {
+        let (px, py) = self.point(anchor, dx, dy);
+        let (fx, fy) = anchor.factors();
+        Rect::new(px - w * fx, py - h * fy, w, h)
+    }
+}
+
+enum Widget {
+    Panel { rect: Rect, fill: &'static str, alpha: f64, border: Option<&'static str> },
+    Label { rect: Rect, text: String, scale: f64, color: &'static str },
+    Button { rect: Rect, text: String, focused: bool, enabled: bool },
+    Bar { rect: Rect, ratio: f64, fill: &'static str, back: &'static str },
+}
+
+// Widgets are collected first and drawn together with render, in order
+#[derive(Default)]
+pub struct Ui {
+    widgets: Vec<Widget>,
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Ui::default()
+    }
+
+    pub fn panel(&mut self, rect: Rect, fill: &'static str, alpha: f64) -> &mut Self {
+        self.widgets.push(Widget::Panel { rect, fill, alpha, border: None });
+        self
+    }
+
+    // Framed box for menus and dialogs, with a title along the top
+    pub fn dialog(&mut self, rect: Rect, title: &str) -> &mut Self {
+        self.widgets.push(Widget::Panel { rect, fill: "#1B1F2A", alpha: 0.92, border: Some(GOLD) });
+        self.label(rect, Anchor::Top, 0.0, 14.0, title, 3.0, GOLD)
+    }
+
+    // Text whose own anchor point is lined up with `anchor` on `area`
+    #[allow(clippy::too_many_arguments)]
+    pub fn label(
+        &mut self,
+        area: Rect,
+        anchor: Anchor,
+        dx: f64,
+        dy: f64,
+        text: &str,
+        scale: f64,
+        color: &'static str,
+    ) -> &mut Self {
+        let (w, h) = font::measure(text, scale);
+        let rect = area.place(anchor, dx, dy, w, h);
+        self.widgets.push(Widget::Label { rect, text: text.to_string(), scale, color });
+        self
+    }
+
+    pub fn button(&mut self, rect: Rect, text: &str, focused: bool, enabled: bool) -> &mut Self {
+        self.widgets.push(Widget::Button { rect, text: text.to_string(), focused, enabled });
+        self
+    }
+
+    pub fn bar(&mut self, rect: Rect, ratio: f64, fill: &'static str, back: &'static str) -> &mut Self {
+        self.widgets.push(Widget::Bar { rect, ratio: ratio.clamp(0.0, 1.0), fill, back });
+        self
+    }
+
+    pub fn render(&self, ctx: &CanvasRenderingContext2d) {
+        for widget in &self.widgets {
+            match widget {
+                Widget::Panel { rect, fill, alpha, border } => {
+                    ctx.set_global_alpha(*alpha);
+                    ctx.set_fill_style_str(fill);
+                    ctx.fill_rect(rect.x, rect.y, rect.w, rect.h);
+                    ctx.set_global_alpha(1.0);
+                    if let Some(border) = border {
+                        draw_frame(ctx, rect, border);
+                    }
+                }
+                Widget::Label { rect, text, scale, color } => {
+                    font::draw_text(ctx, text, rect.x, rect.y, *scale, color);
+                }
+                Widget::Button { rect, text, focused, enabled } => {
+                    ctx.set_global_alpha(if *focused { 0.9 } else { 0.6 });
+                    ctx.set_fill_style_str(if *focused { "#3B4252" } else { "#2E3440" });
+                    ctx.fill_rect(rect.x, rect.y, rect.w, rect.h);
+                    ctx.set_global_alpha(1.0);
+                    draw_frame(ctx, rect, if *focused { GOLD } else { DIM });
+
+                    let color = match (*enabled, *focused) {
+                        (false, _) => DIM,
+                        (true, true) => GOLD,
+                        (true, false) => WHITE,
+                    };
+                    let (w, h) = font::measure(text, 2.0);
+                    let text_rect = rect.place(Anchor::Center, 0.0, 0.0, w, h);
+                    font::draw_text(ctx, text, text_rect.x, text_rect.y, 2.0, color);
+                    if *focused {
+                        font::draw_text(ctx, ">", rect.x + 8.0, text_rect.y, 2.0, GOLD);
+                    }
+                }
+                Widget::Bar { rect, ratio, fill, back } => {
+                    ctx.set_fill_style_str(back);
+                    ctx.fill_rect(rect.x, rect.y, rect.w, rect.h);
+                    ctx.set_fill_style_str(fill);
+                    ctx.fill_rect(rect.x, rect.y, rect.w * ratio, rect.h);
+                }
+            }
+        }
+    }
+}
+
+// Two pixel border drawn with rectangles so it stays crisp
+fn draw_frame(ctx: &CanvasRenderingContext2d, rect: &Rect, color: &str) {
+    ctx.set_fill_style_str(color);
+    ctx.fill_rect(rect.x, rect.y, rect.w, 2.0);
+    ctx.fill_rect(rect.x, rect.y + rect.h - 2.0, rect.w, 2.0);
+    ctx.fill_rect(rect.x, rect.y, 2.0, rect.h);
+    ctx.fill_rect(rect.x + rect.w - 2.0, rect.y, 2.0, rect.h);
+}
+
+// Result of feeding a tick's input to a vertical list of focusable items
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Nav {
+    Stay,
+    Focus(usize),  // Focus moved to this item
+    Activate(usize),
+    Back,
+}
+
+// Up/Down (or the jump and crouch inputs) move the focus and wrap around,
+// Confirm or Attack activates and Pause backs out
+pub fn navigate(focused: usize, count: usize, keys: &KeyState) -> Nav {
+    if keys.pressed(Action::Confirm) || keys.pressed(Action::Attack) {
+        Nav::Activate(focused)
+    } else if keys.pressed(Action::Pause) {
+        Nav::Back
+    } else if count == 0 {
+        Nav::Stay
+    } else if keys.pressed(Action::Jump) {
+        Nav::Focus((focused + count - 1) % count)
+    } else if keys.pressed(Action::Down) {
+        Nav::Focus((focused + 1) % count)
+    } else {
+        Nav::Stay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Input;
+
+    fn tick_with(key: &str) -> KeyState {
+        let mut input = Input::default();
+        input.key_down(key);
+        input.tick().clone()
+    }
+
+    #[test]
+    fn anchored_rects_line_up() {
+        let panel = Rect::new(100.0, 50.0, 200.0, 100.0);
+        assert_eq!(panel.point(Anchor::BottomRight, -10.0, 0.0), (290.0, 150.0));
+        assert_eq!(panel.place(Anchor::Center, 0.0, 0.0, 20.0, 10.0), Rect::new(190.0, 95.0, 20.0, 10.0));
+        assert_eq!(SCREEN.place(Anchor::TopRight, -10.0, 10.0, 50.0, 8.0), Rect::new(740.0, 10.0, 50.0, 8.0));
+    }
+
+    #[test]
+    fn focus_wraps_and_activates() {
+        assert_eq!(navigate(0, 3, &tick_with("ArrowUp")), Nav::Focus(2));
+        assert_eq!(navigate(2, 3, &tick_with("s")), Nav::Focus(0));
+        assert_eq!(navigate(1, 3, &tick_with("Enter")), Nav::Activate(1));
+        assert_eq!(navigate(1, 3, &tick_with("Escape")), Nav::Back);
+        assert_eq!(navigate(1, 3, &KeyState::default()), Nav::Stay);
+    }
+}